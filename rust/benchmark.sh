@@ -4,12 +4,12 @@ CPU_MODEL=`cat /proc/cpuinfo | grep 'model name' | head -n 1 | cut -d ':' -f2 |
 echo "**CPU:** $CPU_MODEL"
 echo
 
-echo "| Day | Least runtime both parts |"
-echo "| --- | ------------------------:|"
+echo "| Day | Median runtime parse and both parts |"
+echo "| --- | -----------------------------------:|"
 
  for DAY in {01..25} ; do
 
-    RESULT=`./run.sh $DAY timeit 2>&1 | grep 'It took' | cut -d ' ' -f3 | sed -e 's/^[[:space:]]*//'`
+    RESULT=`cargo run --release -q -- run --bench --format csv $DAY 2>/dev/null | awk -F ',' 'NR == 1 { for (i = 1; i <= NF; i++) if ($i ~ /_median_ns$/) cols[i] = 1 } NR == 2 { for (i in cols) sum += $i; print sum / 1000000 "ms" }'`
     echo "| $DAY | $RESULT |";
 
 done
\ No newline at end of file
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::output::csv_split;
+use crate::parse::{ParseError, parse_token};
+use crate::{Report, Stats};
+
+/// Relative change of the median below which a difference is never flagged.
+const MIN_CHANGE: f64 = 0.1;
+/// Welch's t statistic above which a difference of means is considered significant.
+const MIN_T: f64 = 3.0;
+
+/// Benchmark results saved from earlier runs, in the CSV format of `Report::to_csv`.
+#[derive(Debug, Default)]
+pub struct Baseline {
+    /// Each day's CSV row, the md5 of its input and the statistics of parsing and both parts.
+    rows: BTreeMap<u32, (String, String, [Stats; 3])>
+}
+
+impl Baseline {
+    /// `bench-baseline.csv` next to `Cargo.toml`.
+    pub fn default_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("bench-baseline.csv")
+    }
+
+    pub fn parse(input: &str) -> Result<Baseline, ParseError> {
+        let mut lines = input.lines().enumerate();
+        let header = Report::csv_header();
+        match lines.next() {
+            Some((_, line)) if line == header => (),
+            Some((_, line)) => return Err(ParseError::at(line, line, "benchmark CSV header").with_line(1)),
+            None => return Ok(Baseline::default())
+        }
+
+        let mut rows = BTreeMap::new();
+        for (i, line) in lines {
+            let fields = csv_split(line);
+            if fields.len() != header.split(',').count() {
+                return Err(ParseError::at(line, line, "benchmark CSV row").with_line(i + 1));
+            }
+
+            let number = |idx: usize| parse_token::<f64>(line, Some(&fields[idx]), "number").map_err(|e| e.with_line(i + 1));
+            let stats = |offset: usize| -> Result<Stats, ParseError> {
+                let samples = number(offset)?.max(1.0);
+                Ok(Stats {
+                    samples: samples as usize,
+                    iterations_per_sample: (number(offset + 1)? / samples).max(1.0) as u64,
+                    min: number(offset + 2)?,
+                    median: number(offset + 3)?,
+                    mean: number(offset + 4)?,
+                    stddev: number(offset + 5)?,
+                    p95: number(offset + 6)?,
+                    max: number(offset + 7)?,
+                    outliers: number(offset + 8)? as usize
+                })
+            };
+
+            let day = number(0)? as u32;
+            rows.insert(day, (line.to_string(), fields[3].clone(), [stats(4)?, stats(13)?, stats(22)?]));
+        }
+
+        Ok(Baseline { rows })
+    }
+
+    /// Loads the baseline at `path`, empty if there is no such file.
+    pub fn load(path: &Path) -> Result<Baseline, String> {
+        match fs::read_to_string(path) {
+            Ok(csv) => Baseline::parse(&csv).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Baseline::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e))
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut csv = Report::csv_header();
+        for (row, _, _) in self.rows.values() {
+            csv.push('\n');
+            csv.push_str(row);
+        }
+        csv.push('\n');
+        fs::write(path, csv)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Replaces the results of the day of `report`.
+    pub fn update(&mut self, report: &Report) {
+        self.rows.insert(report.day, (report.to_csv(), report.input_md5.clone(), [report.parse.clone(), report.part1.clone(), report.part2.clone()]));
+    }
+
+    /// Md5 of the input the baseline of `day` was recorded on.
+    pub fn input_md5(&self, day: u32) -> Option<&str> {
+        self.rows.get(&day).map(|(_, md5, _)| md5.as_str())
+    }
+
+    /// Compares parsing and both parts of `report` with the baseline of its day, if any
+    /// was recorded on the same input, timings of different inputs telling nothing.
+    pub fn compare(&self, report: &Report) -> Option<[Comparison; 3]> {
+        let (_, _, [parse, part1, part2]) = self.rows.get(&report.day).filter(|(_, md5, _)| *md5 == report.input_md5)?;
+        Some([
+            Comparison::new("parse", parse, &report.parse),
+            Comparison::new("part1", part1, &report.part1),
+            Comparison::new("part2", part2, &report.part2)
+        ])
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Change {
+    Slower,
+    Faster,
+    /// Too small, or too noisy, to tell apart from the baseline.
+    Insignificant
+}
+
+/// Change in the time of one step compared to the baseline.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub phase: &'static str,
+    pub before_ns: f64,
+    pub after_ns: f64,
+    /// Relative change of the median, 0.1 meaning 10% slower.
+    pub relative: f64,
+    pub change: Change
+}
+
+impl Comparison {
+    /// Compares medians, flagging differences that are large enough and significant
+    /// according to Welch's t-test on the means.
+    pub fn new(phase: &'static str, before: &Stats, after: &Stats) -> Comparison {
+        let relative = if before.median > 0.0 { after.median / before.median - 1.0 } else { 0.0 };
+        let std_err = (before.stddev.powi(2) / before.samples as f64 + after.stddev.powi(2) / after.samples as f64).sqrt();
+        let significant =
+            relative.abs() >= MIN_CHANGE &&
+            before.samples > 1 && after.samples > 1 &&
+            (std_err == 0.0 || ((after.mean - before.mean) / std_err).abs() > MIN_T);
+
+        let change =
+            if !significant {
+                Change::Insignificant
+            } else if relative > 0.0 {
+                Change::Slower
+            } else {
+                Change::Faster
+            };
+
+        Comparison { phase, before_ns: before.median, after_ns: after.median, relative, change }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}ms -> {}ms, {:+.1}%",
+            self.phase,
+            (self.before_ns / 1_000.0).round() / 1_000.0,
+            (self.after_ns / 1_000.0).round() / 1_000.0,
+            self.relative * 100.0)?;
+        match self.change {
+            Change::Slower => write!(f, ", significantly slower"),
+            Change::Faster => write!(f, ", significantly faster"),
+            Change::Insignificant => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(mean: f64, stddev: f64, samples: usize) -> Stats {
+        Stats { samples, iterations_per_sample: 1, min: mean, median: mean, mean, stddev, p95: mean, max: mean, outliers: 0 }
+    }
+
+    #[test]
+    fn test_compare() {
+        let cmp = Comparison::new("part2", &stats(555.0, 10.0, 20), &stats(700.0, 10.0, 20));
+        assert_eq!(cmp.change, Change::Slower);
+        assert_eq!(cmp.to_string(), "part2: 0.001ms -> 0.001ms, +26.1%, significantly slower");
+        assert_eq!(Comparison::new("parse", &stats(100.0, 1.0, 20), &stats(80.0, 1.0, 20)).change, Change::Faster);
+        assert_eq!(Comparison::new("parse", &stats(100.0, 1.0, 20), &stats(102.0, 1.0, 20)).change, Change::Insignificant);
+        assert_eq!(Comparison::new("parse", &stats(100.0, 80.0, 10), &stats(120.0, 80.0, 10)).change, Change::Insignificant);
+        assert_eq!(Comparison::new("parse", &stats(100.0, 0.0, 1), &stats(200.0, 0.0, 1)).change, Change::Insignificant);
+    }
+
+    #[test]
+    fn test_parse() {
+        let report = Report {
+            day: 15,
+            answers: crate::Answers { part1: "620".to_string(), part2: "a,b".to_string() },
+            parse: stats(100.0, 5.0, 10),
+            part1: stats(2000.0, 50.0, 10),
+            part2: stats(555_000_000.0, 1_000_000.0, 10),
+            input_md5: "x".to_string()
+        };
+        let mut baseline = Baseline::default();
+        baseline.update(&report);
+
+        let csv = format!("{}\n{}\n", Report::csv_header(), report.to_csv());
+        let parsed = Baseline::parse(&csv).unwrap();
+        assert_eq!(parsed.rows[&15].2, baseline.rows[&15].2);
+        assert!(Baseline::parse("day,part1\n").is_err());
+    }
+
+    #[test]
+    fn test_compare_input() {
+        let mut report = Report {
+            day: 1,
+            answers: crate::Answers { part1: "1".to_string(), part2: "2".to_string() },
+            parse: stats(100.0, 1.0, 20),
+            part1: stats(100.0, 1.0, 20),
+            part2: stats(100.0, 1.0, 20),
+            input_md5: "a".to_string()
+        };
+        let mut baseline = Baseline::default();
+        baseline.update(&report);
+        assert!(baseline.compare(&report).is_some());
+
+        report.input_md5 = "b".to_string();
+        report.part1 = stats(1000.0, 1.0, 20);
+        assert!(baseline.compare(&report).is_none());
+        assert_eq!(baseline.input_md5(1), Some("a"));
+    }
+}
@@ -1,61 +1,193 @@
-extern crate utils;
-
-use std::env;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use std::fmt;
+use std::str::FromStr;
+use crate::Solution;
+use crate::parse::{ParseError, parse_lines, parse_token};
 
 type Input = Vec<i32>;
 
-fn solve(input: &Input) -> (i32, i32) {
-    let mut p1 = 0;
-    let mut p2 = 0;
+pub struct Day01;
 
-    for i in 0..input.len() {
-        for j in 0..input.len() {
-            if i == j {
-                continue;
-            }
+impl Solution for Day01 {
+    const DAY: u32 = 1;
+    type Input = Input;
+    type Part1 = KSum;
+    type Part2 = KSum;
 
-            if input[i] + input[j] == 2020 {
-                p1 = input[i] * input[j];
-            }
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, |line| parse_token(line, Some(line), "integer"))
+    }
+
+    fn part1(input: &Input) -> KSum {
+        KSum::find(input, 2, 2020)
+    }
+
+    fn part2(input: &Input) -> KSum {
+        KSum::find(input, 3, 2020)
+    }
+}
+
+/// The entries found by `k_sum`, shown as their product, or which sum there is none for.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum KSum {
+    Found(Vec<i32>),
+    NotFound { k: usize, target: i64 }
+}
+
+impl KSum {
+    pub fn find(input: &[i32], k: usize, target: i64) -> KSum {
+        k_sum(input, k, target).map_or(KSum::NotFound { k, target }, KSum::Found)
+    }
+}
+
+impl fmt::Display for KSum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KSum::Found(entries) => match product(entries) {
+                Some(product) => write!(f, "{}", product),
+                None => write!(f, "product of {} entries overflows", entries.len())
+            },
+            KSum::NotFound { k, target } => write!(f, "no {} entries sum to {}", k, target)
+        }
+    }
+}
+
+/// The product of `entries`, or `None` if it doesn't fit in an i128.
+pub fn product(entries: &[i32]) -> Option<i128> {
+    entries.iter().try_fold(1i128, |product, &e| product.checked_mul(e as i128))
+}
+
+/// How entries with the same value are told apart by `k_sums`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Repeats {
+    /// Entries are distinct by index, so equal values at other indices make other combinations.
+    Indices,
+    /// Combinations of the same values are the same, found once with the first indices of each value.
+    Values
+}
 
-            for k in 0..input.len() {
-                if j == k {
+impl FromStr for Repeats {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "indices" => Ok(Repeats::Indices),
+            "values" => Ok(Repeats::Values),
+            _ => Err(format!("Invalid repeats: {} (expected indices or values)", s))
+        }
+    }
+}
+
+/// Calls `found` with the positions in `sorted` of every `k` entries summing to `target`,
+/// each position used at most once, until it returns false. Fixes the smallest entries
+/// before finding the last two with two pointers moving inwards, taking O(n^(k - 1)) time
+/// for k of 2 and more, plus the number of combinations found.
+fn search<F>(sorted: &[i32], from: usize, k: usize, target: i64, repeats: Repeats, positions: &mut Vec<usize>, found: &mut F) -> bool
+    where F: FnMut(&[usize]) -> bool {
+    let mut report = |positions: &mut Vec<usize>, extra: &[usize]| {
+        let len = positions.len();
+        positions.extend(extra);
+        let more = found(positions);
+        positions.truncate(len);
+        more
+    };
+    let run_end = |from: usize| from + sorted[from..].iter().take_while(|&&e| e == sorted[from]).count();
+
+    match k {
+        0 => target != 0 || report(positions, &[]),
+        1 => {
+            let start = from + sorted[from..].partition_point(|&e| (e as i64) < target);
+            let end = if start < sorted.len() && sorted[start] as i64 == target { run_end(start) } else { start };
+            let end = if repeats == Repeats::Values { end.min(start + 1) } else { end };
+            (start..end).all(|pos| report(positions, &[pos]))
+        },
+        2 if sorted.len() >= from + 2 => {
+            let (mut lo, mut hi) = (from, sorted.len() - 1);
+            while lo < hi {
+                let sum = sorted[lo] as i64 + sorted[hi] as i64;
+                if sum < target {
+                    lo += 1;
+                } else if sum > target {
+                    hi -= 1;
+                } else if sorted[lo] == sorted[hi] {
+                    // Everything from lo to hi is the same value, any two of them will do
+                    let mut pairs = (lo..hi).flat_map(|a| ((a + 1)..=hi).map(move |b| [a, b]));
+                    return match repeats {
+                        Repeats::Indices => pairs.all(|pair| report(positions, &pair)),
+                        Repeats::Values => report(positions, &[lo, lo + 1])
+                    };
+                } else {
+                    let lo_end = run_end(lo);
+                    let hi_start = hi + 1 - sorted[..=hi].iter().rev().take_while(|&&e| e == sorted[hi]).count();
+                    let more = match repeats {
+                        Repeats::Indices => (lo..lo_end).all(|a| (hi_start..=hi).all(|b| report(positions, &[a, b]))),
+                        Repeats::Values => report(positions, &[lo, hi_start])
+                    };
+                    if !more {
+                        return false;
+                    }
+                    lo = lo_end;
+                    hi = hi_start - 1;
+                }
+            }
+            true
+        },
+        _ => {
+            for pos in from..sorted.len() {
+                // All entries from here on are at least as large, so no sum of k of them is smaller
+                if sorted[pos] as i64 * k as i64 > target {
+                    break;
+                }
+                if repeats == Repeats::Values && pos > from && sorted[pos - 1] == sorted[pos] {
                     continue;
                 }
 
-                if input[i] + input[j] + input[k] == 2020 {
-                    p2 = input[i] * input[j] * input[k];
+                positions.push(pos);
+                let more = search(sorted, pos + 1, k - 1, target - sorted[pos] as i64, repeats, positions, found);
+                positions.pop();
+                if !more {
+                    return false;
                 }
             }
+            true
         }
     }
-
-    (p1, p2)
 }
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        let (part1, part2) = solve(&input);
-        println!("Part1: {}", part1);
-        println!("Part2: {}", part2);
-    });
+/// The entries sorted by value, and the index in `input` of each.
+fn sorted(input: &[i32]) -> (Vec<i32>, Vec<usize>) {
+    let mut indices = (0..input.len()).collect::<Vec<_>>();
+    indices.sort_by_key(|&idx| (input[idx], idx));
+    (indices.iter().map(|&idx| input[idx]).collect(), indices)
 }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap().parse::<i32>().unwrap()).collect())
+/// Finds `k` entries, each used at most once, summing to `target`, smallest first.
+pub fn k_sum(input: &[i32], k: usize, target: i64) -> Option<Vec<i32>> {
+    let (sorted, _) = sorted(input);
+    let mut entries = None;
+    search(&sorted, 0, k, target, Repeats::Values, &mut vec![], &mut |positions| {
+        entries = Some(positions.iter().map(|&pos| sorted[pos]).collect());
+        false
+    });
+    entries
 }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+/// The indices of every `k` entries summing to `target`, each index used at most once, with
+/// repeated values told apart according to `repeats`. Each combination has its indices in
+/// increasing order and the combinations are sorted.
+pub fn k_sums(input: &[i32], k: usize, target: i64, repeats: Repeats) -> Vec<Vec<usize>> {
+    let (sorted, indices) = sorted(input);
+    let mut combinations = vec![];
+    search(&sorted, 0, k, target, repeats, &mut vec![], &mut |positions| {
+        let mut combination = positions.iter().map(|&pos| indices[pos]).collect::<Vec<_>>();
+        combination.sort_unstable();
+        combinations.push(combination);
+        true
+    });
+    combinations.sort_unstable();
+    combinations
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -66,16 +198,61 @@ mod tests {
         20";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day01::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(solve(&as_input(INPUT)).0, 1000 * 1020);
+        assert_eq!(Day01::part1(&as_input(INPUT)), KSum::Found(vec![1000, 1020]));
+        assert_eq!(Day01::part1(&as_input(INPUT)).to_string(), (1000 * 1020).to_string());
+        assert_eq!(Day01::part1(&as_input("1010\n1")).to_string(), "no 2 entries sum to 2020");
+        // The product doesn't fit in an i64
+        assert_eq!(Day01::part2(&as_input("2000000000\n-1999999000\n1020")).to_string(), "-4079997960000000000000");
+    }
+
+    #[test]
+    fn test_product() {
+        assert_eq!(product(&[]), Some(1));
+        assert_eq!(product(&[-3, 1000, -7]), Some(21000));
+        assert_eq!(product(&[i32::MIN; 3]), Some(-(1i128 << 93)));
+        assert_eq!(product(&[i32::MIN; 5]), None);
+        assert_eq!(KSum::Found(vec![i32::MAX; 5]).to_string(), "product of 5 entries overflows");
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(solve(&as_input(INPUT)).1, 1000 * 1000 * 20);
+        assert_eq!(Day01::part2(&as_input(INPUT)).to_string(), (1000 * 1000 * 20).to_string());
+        // Each entry can only be used once, 1000 + 1000 + 20 needs both of them
+        assert_eq!(Day01::part2(&as_input("1000\n20\n1")), KSum::NotFound { k: 3, target: 2020 });
+    }
+
+    #[test]
+    fn test_k_sum() {
+        let input = as_input(INPUT);
+        assert_eq!(k_sum(&input, 2, 2020), Some(vec![1000, 1020]));
+        assert_eq!(k_sum(&input, 2, 2000), Some(vec![1000, 1000]));
+        assert_eq!(k_sum(&input, 4, 3040), Some(vec![20, 1000, 1000, 1020]));
+        assert_eq!(k_sum(&input, 1, 20), Some(vec![20]));
+        assert_eq!(k_sum(&input, 2, 40), None);
+        assert_eq!(k_sum(&input, 5, 3040), None);
+        assert_eq!(k_sum(&[-5, 3, 10, -2], 3, 3), Some(vec![-5, -2, 10]));
+
+        let input = (0..100_000).rev().map(|i| i * 3).collect::<Vec<_>>();
+        assert_eq!(k_sum(&input, 2, 599_991), Some(vec![299_994, 299_997]));
+    }
+
+    #[test]
+    fn test_k_sums() {
+        let input = as_input("1000\n1020\n1000\n20\n1000\n1010\n1010");
+        assert_eq!(k_sums(&input, 2, 2020, Repeats::Indices), vec![vec![0, 1], vec![1, 2], vec![1, 4], vec![5, 6]]);
+        assert_eq!(k_sums(&input, 2, 2020, Repeats::Values), vec![vec![0, 1], vec![5, 6]]);
+        assert_eq!(k_sums(&input, 2, 2000, Repeats::Indices), vec![vec![0, 2], vec![0, 4], vec![2, 4]]);
+        assert_eq!(k_sums(&input, 2, 2000, Repeats::Values), vec![vec![0, 2]]);
+        assert_eq!(k_sums(&input, 3, 2020, Repeats::Indices), vec![vec![0, 2, 3], vec![0, 3, 4], vec![2, 3, 4]]);
+        assert_eq!(k_sums(&input, 3, 2020, Repeats::Values), vec![vec![0, 2, 3]]);
+        assert_eq!(k_sums(&input, 1, 1000, Repeats::Indices), vec![vec![0], vec![2], vec![4]]);
+        assert_eq!(k_sums(&input, 3, 3000, Repeats::Values), vec![vec![0, 2, 4]]);
+        assert!(k_sums(&input, 2, 1, Repeats::Indices).is_empty());
+        assert_eq!(k_sums(&input, 0, 0, Repeats::Values), vec![Vec::<usize>::new()]);
     }
 }
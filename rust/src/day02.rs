@@ -1,20 +1,16 @@
-extern crate regex;
-#[macro_use] extern crate lazy_static;
-extern crate utils;
-
-use std::env;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::str::FromStr;
-use std::num::ParseIntError;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
 use regex::Regex;
-use utils::*;
+use crate::Solution;
+use crate::output::json_string;
+use crate::parse::{ParseError, parse_lines, parse_token, expect_token};
 
 type Input = Vec<Row>;
 
 #[derive(Debug)]
-struct Row {
+pub struct Row {
     policy: Policy,
     password: String
 }
@@ -26,82 +22,515 @@ struct Policy {
     chr: char
 }
 
-impl Policy {
+/// A rule for the password of a row, which can use the row's own policy.
+pub trait PasswordPolicy: fmt::Display + Send + Sync {
+    /// Checks the password of `row`, describing the rule it broke and how if it doesn't conform.
+    fn check(&self, row: &Row) -> Result<(), String>;
 
-    fn conforms1(&self, password: &str) -> bool {
-        let mut counts: [u8; 256] = [0; 256];
+    fn conforms(&self, row: &Row) -> bool {
+        self.check(row).is_ok()
+    }
+}
 
-        for chr in password.chars() {
-            counts[chr as usize] += 1;
+/// The row's character must appear from `from` to `to` times.
+pub struct CountRange;
+
+impl PasswordPolicy for CountRange {
+    fn check(&self, row: &Row) -> Result<(), String> {
+        let Policy { from, to, chr } = row.policy;
+        let count = row.password.chars().filter(|&c| c == chr).count();
+        if (from..=to).contains(&count) {
+            Ok(())
+        } else {
+            Err(format!("{}: {:?} appears {} times, not {} to {}", self, chr, count, from, to))
         }
+    }
+}
 
-       let chr_count = counts[self.chr as usize] as usize;
-       chr_count >= self.from && chr_count <= self.to
+impl fmt::Display for CountRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "count")
     }
+}
 
-    fn conforms2(&self, password: &str) -> bool {
-        let chars: Vec<_> = password.chars().collect();
+/// The row's character must be at exactly one of the 1-based positions `from` and `to`,
+/// a position past the end of the password not having it.
+pub struct ExactlyOnePosition;
 
-        let mut cnt = 0;
-        if chars[self.from - 1] == self.chr {
-            cnt += 1;
+impl PasswordPolicy for ExactlyOnePosition {
+    fn check(&self, row: &Row) -> Result<(), String> {
+        let Policy { from, to, chr } = row.policy;
+        let at = |pos: usize| pos > 0 && row.password.chars().nth(pos - 1) == Some(chr);
+        match (at(from), at(to)) {
+            (true, false) | (false, true) => Ok(()),
+            (true, true) => Err(format!("{}: {:?} is at both {} and {}", self, chr, from, to)),
+            (false, false) => Err(format!("{}: {:?} is at neither {} nor {}", self, chr, from, to))
         }
-        if chars[self.to - 1] == self.chr {
-            cnt += 1;
+    }
+}
+
+impl fmt::Display for ExactlyOnePosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "positions")
+    }
+}
+
+/// The password must match the regular expression.
+pub struct Matches(pub Regex);
+
+impl PasswordPolicy for Matches {
+    fn check(&self, row: &Row) -> Result<(), String> {
+        if self.0.is_match(&row.password) {
+            Ok(())
+        } else {
+            Err(format!("{}: no match", self))
         }
+    }
+}
 
-        cnt == 1
+impl fmt::Display for Matches {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "regex({})", quote(self.0.as_str()))
     }
 }
 
-fn solve(input: &Input) -> (usize, usize) {
-    return input.iter()
-        .fold((0, 0), |(p1, p2), row| {
-            (
-                p1 + if row.policy.conforms1(&row.password) { 1 } else { 0 },
-                p2 + if row.policy.conforms2(&row.password) { 1 } else { 0 }
-            )
-        });
+/// The password must have at least this many bits of Shannon entropy, its length
+/// times the entropy of the frequencies of its characters.
+pub struct MinEntropy(pub f64);
+
+/// Bits of Shannon entropy of `password`.
+pub fn entropy(password: &str) -> f64 {
+    let mut counts = HashMap::new();
+    for chr in password.chars() {
+        *counts.entry(chr).or_insert(0) += 1;
+    }
+
+    let len = counts.values().sum::<usize>() as f64;
+    let per_char = counts.values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            p * (1.0 / p).log2()
+        })
+        .sum::<f64>();
+    per_char * len
 }
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        let (part1, part2) = solve(&input);
-        println!("Part1: {}", part1);
-        println!("Part2: {}", part2);
-    });
+impl PasswordPolicy for MinEntropy {
+    fn check(&self, row: &Row) -> Result<(), String> {
+        let bits = entropy(&row.password);
+        if bits >= self.0 {
+            Ok(())
+        } else {
+            Err(format!("{}: only {:.2} bits", self, bits))
+        }
+    }
 }
 
-impl FromStr for Row {
-    type Err = ParseIntError;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^(\d+)-(\d+) (.): (.+)$").unwrap();
+impl fmt::Display for MinEntropy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entropy({})", self.0)
+    }
+}
+
+/// The password must not contain any of the substrings.
+pub struct Forbidden(pub Vec<String>);
+
+impl PasswordPolicy for Forbidden {
+    fn check(&self, row: &Row) -> Result<(), String> {
+        match self.0.iter().find(|s| row.password.contains(s.as_str())) {
+            Some(s) => Err(format!("{}: contains {:?}", self, s)),
+            None => Ok(())
         }
-        let caps = RE.captures(s).unwrap();
+    }
+}
 
-        Ok(Row {
-            policy: Policy {
-                from: caps[1].parse::<usize>()?,
-                to: caps[2].parse::<usize>()?,
-                chr: caps[3].chars().next().unwrap()
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let substrings = self.0.iter().map(|s| quote(s)).collect::<Vec<_>>();
+        write!(f, "forbid({})", substrings.join(", "))
+    }
+}
+
+/// Every one of the policies must be followed, reporting the first one broken.
+pub struct All(pub Vec<Box<dyn PasswordPolicy>>);
+
+impl PasswordPolicy for All {
+    fn check(&self, row: &Row) -> Result<(), String> {
+        self.0.iter().try_for_each(|policy| policy.check(row))
+    }
+}
+
+/// Any of the policies must be followed, reporting all of them when none is.
+pub struct Any(pub Vec<Box<dyn PasswordPolicy>>);
+
+impl PasswordPolicy for Any {
+    fn check(&self, row: &Row) -> Result<(), String> {
+        let mut broken = vec![];
+        for policy in &self.0 {
+            match policy.check(row) {
+                Ok(()) => return Ok(()),
+                Err(e) => broken.push(e)
+            }
+        }
+        Err(format!("{}: {}", self, broken.join("; ")))
+    }
+}
+
+/// The policy must be broken.
+pub struct Not(pub Box<dyn PasswordPolicy>);
+
+impl PasswordPolicy for Not {
+    fn check(&self, row: &Row) -> Result<(), String> {
+        match self.0.check(row) {
+            Ok(()) => Err(format!("{}: {} is followed", self, self.0)),
+            Err(_) => Ok(())
+        }
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, name: &str, policies: &[Box<dyn PasswordPolicy>]) -> fmt::Result {
+    let policies = policies.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+    write!(f, "{}({})", name, policies.join(", "))
+}
+
+impl fmt::Display for All {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_list(f, "and", &self.0)
+    }
+}
+
+impl fmt::Display for Any {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_list(f, "or", &self.0)
+    }
+}
+
+impl fmt::Display for Not {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not({})", self.0)
+    }
+}
+
+/// Quotes `s` the way `parse_policy` reads strings, escaping only `\\` and `"`.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Parses a policy written like its `Display`: `count`, `positions`, `regex("...")`,
+/// `entropy(<bits>)`, `forbid("...", ...)` or policies combined with `and(...)`, `or(...)`
+/// and `not(...)`, strings being quoted with `\\` and `\"` escapes, any other `\` being
+/// kept as is for regex escapes like `\d`.
+pub fn parse_policy(s: &str) -> Result<Box<dyn PasswordPolicy>, ParseError> {
+    let mut parser = PolicyParser { s, pos: 0 };
+    let policy = parser.policy()?;
+    parser.skip_whitespace();
+    match &s[parser.pos..] {
+        "" => Ok(policy),
+        rest => Err(ParseError::at(s, rest, "end of policy"))
+    }
+}
+
+struct PolicyParser<'a> {
+    s: &'a str,
+    pos: usize
+}
+
+impl<'a> PolicyParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.s.len() - self.rest().trim_start().len();
+    }
+
+    fn error(&self, expected: &str) -> ParseError {
+        let rest = self.rest();
+        match rest.chars().next() {
+            Some(chr) => ParseError::at(self.s, &rest[..chr.len_utf8()], expected),
+            None => ParseError::at_end(self.s, expected)
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", token)))
+        }
+    }
+
+    /// Parses `item` repeatedly, separated by commas, up to the closing parenthesis.
+    fn list<T, F>(&mut self, mut item: F) -> Result<Vec<T>, ParseError> where F: FnMut(&mut Self) -> Result<T, ParseError> {
+        let mut items = vec![item(self)?];
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with(',') {
+                self.pos += 1;
+                items.push(item(self)?);
+            } else {
+                self.expect(")")?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.expect("\"")?;
+        let mut string = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((idx, chr)) = chars.next() {
+            match chr {
+                '"' => {
+                    self.pos += idx + 1;
+                    return Ok(string);
+                },
+                '\\' => match chars.next() {
+                    Some((_, chr)) if chr == '"' || chr == '\\' => string.push(chr),
+                    Some((_, chr)) => {
+                        string.push('\\');
+                        string.push(chr);
+                    },
+                    None => break
+                },
+                chr => string.push(chr)
+            }
+        }
+        self.pos = self.s.len();
+        Err(self.error("closing '\"'"))
+    }
+
+    fn policy(&mut self) -> Result<Box<dyn PasswordPolicy>, ParseError> {
+        self.skip_whitespace();
+        let name_len = self.rest().find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(self.rest().len());
+        let start = self.pos;
+        let name = &self.rest()[..name_len];
+        self.pos += name_len;
+
+        let policy: Box<dyn PasswordPolicy> = match name {
+            "count" => Box::new(CountRange),
+            "positions" => Box::new(ExactlyOnePosition),
+            "regex" => {
+                self.expect("(")?;
+                let pattern_pos = self.pos;
+                let pattern = self.string()?;
+                let regex = Regex::new(&pattern).map_err(|_| {
+                    self.pos = pattern_pos;
+                    self.skip_whitespace();
+                    self.error("valid regular expression")
+                })?;
+                self.expect(")")?;
+                Box::new(Matches(regex))
             },
-            password: caps[4].to_string()
-        })
+            "entropy" => {
+                self.expect("(")?;
+                self.skip_whitespace();
+                let len = self.rest().find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(self.rest().len());
+                if len == 0 {
+                    return Err(self.error("number of bits"));
+                }
+                let bits = parse_token(self.s, Some(&self.rest()[..len]), "number of bits")?;
+                self.pos += len;
+                self.expect(")")?;
+                Box::new(MinEntropy(bits))
+            },
+            "forbid" => {
+                self.expect("(")?;
+                Box::new(Forbidden(self.list(Self::string)?))
+            },
+            "and" | "or" => {
+                self.expect("(")?;
+                let policies = self.list(Self::policy)?;
+                if name == "and" { Box::new(All(policies)) } else { Box::new(Any(policies)) }
+            },
+            "not" => {
+                self.expect("(")?;
+                let policy = self.policy()?;
+                self.expect(")")?;
+                Box::new(Not(policy))
+            },
+            _ => {
+                self.pos = start;
+                return Err(self.error("policy 'count', 'positions', 'regex', 'entropy', 'forbid', 'and', 'or' or 'not'"));
+            }
+        };
+        Ok(policy)
     }
 }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap().parse::<Row>().unwrap()).collect())
+/// Summary of a password database: how rows fare under both policies, and what they look like.
+#[derive(Debug)]
+pub struct Audit {
+    pub rows: usize,
+    /// Each policy with how many rows pass and fail it.
+    pub policies: Vec<(String, usize, usize)>,
+    /// Rows for each range of a policy, by range.
+    pub ranges: BTreeMap<(usize, usize), usize>,
+    /// Rows for each policy character, most common first.
+    pub chars: Vec<(char, usize)>,
+    /// Line and row of those following `count` but not `positions`.
+    pub only_count: Vec<(usize, String)>,
+    /// Line and row of those following `positions` but not `count`.
+    pub only_positions: Vec<(usize, String)>,
+    /// Line and row of those with a position past the end of their password.
+    pub out_of_range: Vec<(usize, String)>
 }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+impl Audit {
+    pub fn new(rows: &[Row]) -> Audit {
+        let mut audit = Audit {
+            rows: rows.len(),
+            policies: vec![],
+            ranges: BTreeMap::new(),
+            chars: vec![],
+            only_count: vec![],
+            only_positions: vec![],
+            out_of_range: vec![]
+        };
+        let (mut count_passed, mut positions_passed) = (0, 0);
+        let mut chars = HashMap::new();
+
+        for (i, row) in rows.iter().enumerate() {
+            let Policy { from, to, chr } = row.policy;
+            *audit.ranges.entry((from, to)).or_insert(0) += 1;
+            *chars.entry(chr).or_insert(0) += 1;
+
+            let line = (i + 1, row.to_string());
+            match (CountRange.conforms(row), ExactlyOnePosition.conforms(row)) {
+                (true, true) => { count_passed += 1; positions_passed += 1 },
+                (true, false) => { count_passed += 1; audit.only_count.push(line.clone()) },
+                (false, true) => { positions_passed += 1; audit.only_positions.push(line.clone()) },
+                (false, false) => ()
+            }
+            let len = row.password.chars().count();
+            if from == 0 || from > len || to == 0 || to > len {
+                audit.out_of_range.push(line);
+            }
+        }
+
+        audit.policies = vec![
+            (CountRange.to_string(), count_passed, rows.len() - count_passed),
+            (ExactlyOnePosition.to_string(), positions_passed, rows.len() - positions_passed)
+        ];
+        audit.chars = chars.into_iter().collect();
+        audit.chars.sort_by_key(|&(chr, rows)| (Reverse(rows), chr));
+        audit
+    }
+
+    /// Tables of the policies, ranges and characters followed by the rows of note.
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("Rows: {}", self.rows), String::new(), format!("{:<12}{:>8}{:>8}", "Policy", "Passed", "Failed")];
+        for (policy, passed, failed) in &self.policies {
+            lines.push(format!("{:<12}{:>8}{:>8}", policy, passed, failed));
+        }
+
+        lines.push(String::new());
+        lines.push(format!("{:<12}{:>8}", "Range", "Rows"));
+        for ((from, to), rows) in &self.ranges {
+            lines.push(format!("{:<12}{:>8}  {}", format!("{}-{}", from, to), rows, "#".repeat(*rows)));
+        }
+
+        lines.push(String::new());
+        lines.push(format!("{:<12}{:>8}", "Character", "Rows"));
+        for (chr, rows) in &self.chars {
+            lines.push(format!("{:<12}{:>8}", chr, rows));
+        }
+
+        for (title, rows) in [
+            ("Following count but not positions", &self.only_count),
+            ("Following positions but not count", &self.only_positions),
+            ("Positions past the end of the password", &self.out_of_range)
+        ] {
+            lines.push(String::new());
+            lines.push(format!("{}: {}", title, rows.len()));
+            lines.extend(rows.iter().map(|(line, row)| format!("  line {}: {}", line, row)));
+        }
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        let list = |items: Vec<String>| format!("[{}]", items.join(","));
+        let rows = |rows: &[(usize, String)]| list(rows.iter()
+            .map(|(line, row)| format!("{{\"line\":{},\"row\":{}}}", line, json_string(row)))
+            .collect());
+
+        let policies = self.policies.iter()
+            .map(|(policy, passed, failed)| format!("{{\"policy\":{},\"passed\":{},\"failed\":{}}}", json_string(policy), passed, failed))
+            .collect();
+        let ranges = self.ranges.iter()
+            .map(|((from, to), rows)| format!("{{\"from\":{},\"to\":{},\"rows\":{}}}", from, to, rows))
+            .collect();
+        let chars = self.chars.iter()
+            .map(|(chr, rows)| format!("{{\"character\":{},\"rows\":{}}}", json_string(&chr.to_string()), rows))
+            .collect();
+
+        format!(
+            "{{\"rows\":{},\"policies\":{},\"ranges\":{},\"characters\":{},\"only_count\":{},\"only_positions\":{},\"out_of_range\":{}}}",
+            self.rows, list(policies), list(ranges), list(chars), rows(&self.only_count), rows(&self.only_positions), rows(&self.out_of_range)
+        )
+    }
+}
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    const DAY: u32 = 2;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, str::parse::<Row>)
+    }
+
+    fn part1(input: &Input) -> usize {
+        input.iter()
+            .filter(|row| CountRange.conforms(row))
+            .count()
+    }
+
+    fn part2(input: &Input) -> usize {
+        input.iter()
+            .filter(|row| ExactlyOnePosition.conforms(row))
+            .count()
+    }
+}
+
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{} {}: {}", self.policy.from, self.policy.to, self.policy.chr, self.password)
+    }
+}
+
+impl FromStr for Row {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ": ");
+        let mut policy = parts.next().unwrap_or(s).splitn(2, ' ');
+        let mut range = policy.next().unwrap_or(s).splitn(2, '-');
+
+        let from = parse_token(s, range.next(), "policy range start")?;
+        let to = parse_token(s, range.next(), "'-' followed by policy range end")?;
+        let chr = expect_token(s, policy.next(), "policy character")?;
+        let password = expect_token(s, parts.next().filter(|p| !p.is_empty()), "': ' followed by password")?;
+
+        let mut chrs = chr.chars();
+        let chr = match (chrs.next(), chrs.next()) {
+            (Some(c), None) => c,
+            _ => return Err(ParseError::at(s, chr, "single policy character"))
+        };
+
+        Ok(Row {
+            policy: Policy { from, to, chr },
+            password: password.to_string()
+        })
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -111,16 +540,79 @@ mod tests {
         2-9 c: ccccccccc";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day02::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(solve(&as_input(INPUT)).0, 2);
+        assert_eq!(Day02::part1(&as_input(INPUT)), 2);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(solve(&as_input(INPUT)).1, 1);
+        assert_eq!(Day02::part2(&as_input(INPUT)), 1);
+    }
+
+    #[test]
+    fn test_audit() {
+        let audit = Audit::new(&as_input("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n1-3 c: cc"));
+        assert_eq!(audit.policies, vec![("count".to_string(), 3, 1), ("positions".to_string(), 2, 2)]);
+        assert_eq!(audit.ranges.into_iter().collect::<Vec<_>>(), vec![((1, 3), 3), ((2, 9), 1)]);
+        assert_eq!(audit.chars, vec![('c', 2), ('a', 1), ('b', 1)]);
+        assert_eq!(audit.only_count, vec![(3, "2-9 c: ccccccccc".to_string())]);
+        assert!(audit.only_positions.is_empty());
+        assert_eq!(audit.out_of_range, vec![(4, "1-3 c: cc".to_string())]);
+
+        let audit = Audit::new(&as_input("1-2 x: ab"));
+        assert_eq!(audit.to_json(), "{\"rows\":1,\"policies\":[{\"policy\":\"count\",\"passed\":0,\"failed\":1},{\"policy\":\"positions\",\"passed\":0,\"failed\":1}],\
+            \"ranges\":[{\"from\":1,\"to\":2,\"rows\":1}],\"characters\":[{\"character\":\"x\",\"rows\":1}],\
+            \"only_count\":[],\"only_positions\":[],\"out_of_range\":[]}");
+    }
+
+    fn failures(policy: &str, input: &str) -> Vec<String> {
+        let policy = parse_policy(policy).unwrap();
+        as_input(input).iter().filter_map(|row| policy.check(row).err()).collect()
+    }
+
+    #[test]
+    fn test_policies() {
+        assert_eq!(failures("count", INPUT), vec!["count: 'b' appears 0 times, not 1 to 3"]);
+        assert_eq!(failures("positions", INPUT), vec!["positions: 'b' is at neither 1 nor 3", "positions: 'c' is at both 2 and 9"]);
+        // Positions past the end of the password and characters beyond ASCII
+        assert_eq!(failures("and(count, positions)", "3-9 é: éé\n1-2 ✓: ✓✓\n1-9 x: x"), vec!["count: 'é' appears 2 times, not 3 to 9", "positions: '✓' is at both 1 and 2"]);
+
+        assert_eq!(failures("regex(\"^[a-e]+$\")", INPUT), vec!["regex(\"^[a-e]+$\"): no match"]);
+        assert_eq!(failures("entropy(2)", INPUT), vec!["entropy(2): only 0.00 bits"]);
+        assert_eq!(failures("forbid(\"cde\", \"x\")", INPUT), vec!["forbid(\"cde\", \"x\"): contains \"cde\"", "forbid(\"cde\", \"x\"): contains \"cde\""]);
+        assert_eq!(failures("or(positions, not(count))", INPUT), vec!["or(positions, not(count)): positions: 'c' is at both 2 and 9; not(count): count is followed"]);
+        assert!((entropy("abcd") - 8.0).abs() < 1e-9);
+
+        let policy = "and(count, or(regex(\"\\\\d\\\"\"), not(entropy(2.5))), forbid(\"ab\"))";
+        assert_eq!(parse_policy(policy).unwrap().to_string(), policy);
+        assert_eq!(parse_policy("and(count, nope)").err().map(|e| e.to_string()).unwrap_or_default(), "column 12: expected policy 'count', 'positions', 'regex', 'entropy', 'forbid', 'and', 'or' or 'not', found \"n\"");
+        assert!(parse_policy("regex(\"(\")").is_err());
+        assert!(parse_policy("count)").is_err());
+        assert!(parse_policy("entropy(x)").is_err());
+        assert!(parse_policy("forbid(\"x)").is_err());
+    }
+
+    #[test]
+    fn test_policy_escapes() {
+        assert_eq!(failures(r#"regex("^\d")"#, "1-3 a: 1bc\n1-3 a: dbc"), vec![r#"regex("^\\d"): no match"#]);
+        assert_eq!(failures(r#"regex("a\nb")"#, "1-3 a: anb"), vec![r#"regex("a\\nb"): no match"#]);
+        for policy in [r#"regex("^\\d\"")"#, r#"forbid("\\", "x\"y")"#, r#"regex("\\\\")"#] {
+            assert_eq!(parse_policy(policy).unwrap().to_string(), policy);
+        }
+        let policy = parse_policy(r#"regex("\d")"#).unwrap();
+        assert_eq!(policy.to_string(), r#"regex("\\d")"#);
+        assert_eq!(parse_policy(&policy.to_string()).unwrap().to_string(), policy.to_string());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = |s: &str| Day02::parse(s).unwrap_err().to_string();
+        assert_eq!(error("1-3 a: abcde\n1-x b: cdefg"), "line 2, column 3: expected '-' followed by policy range end, found \"x\"");
+        assert_eq!(error("1-3 ab: abcde"), "line 1, column 5: expected single policy character, found \"ab\"");
+        assert_eq!(error("1-3 a abcde"), "line 1, column 12: expected ': ' followed by password, found end of line");
     }
 }
@@ -1,99 +1,173 @@
-extern crate utils;
+use std::fmt;
+use crate::Solution;
+use crate::geom::Vec2;
+use crate::grid::{Grid, Edges};
+use crate::parse::{ParseError, parse_token};
 
-use std::env;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
-
-type Input = Map;
+type Input = Grid<MapType>;
 
 #[derive(Eq, PartialEq, Clone,Copy, Debug)]
-enum MapType {
+pub enum MapType {
     Tree, Open
 }
 
-#[derive(Debug)]
-struct Map {
-    rows: Vec<Vec<MapType>>
+/// The slopes of part 2, as right and down steps.
+pub const SLOPES: [Vec2<isize>; 5] = [
+    Vec2 { x: 1, y: 1 },
+    Vec2 { x: 3, y: 1 },
+    Vec2 { x: 5, y: 1 },
+    Vec2 { x: 7, y: 1 },
+    Vec2 { x: 1, y: 2 }
+];
+
+/// The positions visited going down `map` from the top left by `slope`, which must go down.
+/// The map repeats to the right as well as the left for slopes going left.
+pub fn path(map: &Input, slope: Vec2<isize>) -> impl Iterator<Item = Vec2<isize>> {
+    assert!(slope.y > 0, "Slope {} doesn't go down", slope);
+    let height = map.height() as isize;
+    (0..)
+        .map(move |step| slope * step)
+        .take_while(move |pos| pos.y < height)
+}
+
+pub fn num_trees_hit(map: &Input, slope: Vec2<isize>) -> usize {
+    path(map, slope)
+        .filter(|pos| map[(pos.x, pos.y)] == MapType::Tree)
+        .count()
 }
 
-impl Map {
-    fn at(&self, x: usize, y: usize) -> Option<MapType> {
-        if y >= self.rows.len() {
-            None
-        } else {
-            let row = &self.rows[y];
-            Some(row[x % row.len()])
+/// A square of the map as rendered by `render`.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Rendered {
+    Tree,
+    Open,
+    /// A tree hit on the way down.
+    Hit,
+    /// An open square passed on the way down.
+    Visited
+}
+
+impl Rendered {
+    pub fn color(self) -> [u8; 3] {
+        match self {
+            Rendered::Tree => [34, 139, 34],
+            Rendered::Open => [255, 255, 255],
+            Rendered::Hit => [220, 20, 60],
+            Rendered::Visited => [30, 144, 255]
         }
     }
+}
 
-    fn num_trees_hit(&self, slope_x: usize, slope_y: usize) -> usize {
-        let mut num_trees = 0;
-        let mut x = 0;
-        let mut y = 0;
+impl fmt::Display for Rendered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chr = match self {
+            Rendered::Tree => '#',
+            Rendered::Open => '.',
+            Rendered::Hit => 'X',
+            Rendered::Visited => 'O'
+        };
+        write!(f, "{}", chr)
+    }
+}
 
-        for _ in 0.. {
-            if let Some(mt) = self.at(x, y) {
-                num_trees += if mt == MapType::Tree { 1 } else { 0 };
-            } else {
-                break;
-            }
+/// The map repeated as many times as the path down it by `slope` needs, to the left for
+/// slopes going left, with the squares passed marked like in the puzzle.
+pub fn render(map: &Input, slope: Vec2<isize>) -> Grid<Rendered> {
+    if map.width() == 0 {
+        return Grid::from_cells(0, vec![]);
+    }
+    let path = path(map, slope).collect::<Vec<_>>();
+    let width = map.width() as isize;
+    let min_x = path.iter().map(|pos| pos.x).min().unwrap_or(0).div_euclid(width) * width;
+    let max_x = (path.iter().map(|pos| pos.x).max().unwrap_or(0).div_euclid(width) + 1) * width;
+
+    let mut rendered = Grid::from_cells((max_x - min_x) as usize, (0..map.height() as isize)
+        .flat_map(|y| (min_x..max_x).map(move |x| (x, y)))
+        .map(|pos| if map[pos] == MapType::Tree { Rendered::Tree } else { Rendered::Open })
+        .collect());
+    for pos in path {
+        let cell = &mut rendered[(pos.x - min_x, pos.y)];
+        *cell = if *cell == Rendered::Tree { Rendered::Hit } else { Rendered::Visited };
+    }
+    rendered
+}
 
-            x += slope_x;
-            y += slope_y;
+/// Parses slopes written as `right,down`, separated by whitespace or on lines of their own,
+/// with `#` starting a comment.
+pub fn parse_slopes(s: &str) -> Result<Vec<Vec2<isize>>, ParseError> {
+    let mut slopes = vec![];
+    for (i, line) in s.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        for token in line.split_whitespace() {
+            slopes.push(slope_at(line, token).map_err(|e| e.with_line(i + 1))?);
         }
-
-        num_trees
     }
+    Ok(slopes)
 }
 
-fn part1(input: &Input) -> usize {
-    input.num_trees_hit(3, 1)
+/// Parses a slope written as `right,down`, right being negative to go left.
+pub fn parse_slope(s: &str) -> Result<Vec2<isize>, ParseError> {
+    slope_at(s, s)
 }
 
-fn part2(input: &Input) -> usize {
-    let slopes = vec![
-        (1_usize, 1_usize),
-        (3, 1),
-        (5, 1),
-        (7, 1),
-        (1, 2)
-    ];
-
-    slopes.iter()
-        .map(|(s_x, s_y)| input.num_trees_hit(*s_x, *s_y))
-        .product()
+/// Parses `token`, a slice of `line`, as a slope.
+fn slope_at(line: &str, token: &str) -> Result<Vec2<isize>, ParseError> {
+    let mut parts = token.splitn(2, ',');
+    let right = parse_token(line, parts.next(), "steps right")?;
+    let down_token = parts.next();
+    let down = parse_token(line, down_token, "',' followed by steps down")?;
+    if down <= 0 {
+        return Err(ParseError::at(line, down_token.unwrap_or(token), "positive steps down"));
+    }
+    Ok(Vec2::new(right, down))
 }
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        println!("Part1: {}", part1(&input));
-        println!("Part2: {}", part2(&input));
+/// Every slope going from `max_left` left to `max_right` right and 1 to `max_down` down
+/// with the trees hit, fewest first, or most first if `most`. Equal ones are ordered by
+/// slope, steepest and leftmost first.
+pub fn rank_slopes(map: &Input, max_left: isize, max_right: isize, max_down: isize, most: bool) -> Vec<(Vec2<isize>, usize)> {
+    let mut ranking = (1..=max_down)
+        .flat_map(|down| (-max_left..=max_right).map(move |right| Vec2::new(right, down)))
+        .map(|slope| (slope, num_trees_hit(map, slope)))
+        .collect::<Vec<_>>();
+    ranking.sort_by(|(a, a_trees), (b, b_trees)| {
+        let trees = if most { b_trees.cmp(a_trees) } else { a_trees.cmp(b_trees) };
+        trees.then(b.y.cmp(&a.y)).then(a.x.cmp(&b.x))
     });
+    ranking
 }
 
+pub struct Day03;
+
+impl Solution for Day03 {
+    const DAY: u32 = 3;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    let mut rows: Vec<Vec<MapType>> = vec![];
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        let map = Grid::parse(input, "'#' or '.'", |chr| match chr {
+            '#' => Some(MapType::Tree),
+            '.' => Some(MapType::Open),
+            _ => None
+        })?;
 
-    for line in reader.lines().map(|l| l.unwrap()) {
-        let row: Vec<_> = line.chars()
-            .map(|chr| if chr == '#' { MapType::Tree } else { MapType::Open })
-            .collect();
-        rows.push(row);
+        Ok(map.with_edges(Edges::Wrapping))
     }
 
-    Ok(Map { rows })
-}
+    fn part1(input: &Input) -> usize {
+        num_trees_hit(input, Vec2::new(3, 1))
+    }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+    fn part2(input: &Input) -> usize {
+        SLOPES.iter()
+            .map(|&slope| num_trees_hit(input, slope))
+            .product()
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -111,16 +185,50 @@ mod tests {
         .#..#...#.#";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day03::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&as_input(INPUT)), 7);
+        assert_eq!(Day03::part1(&as_input(INPUT)), 7);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&as_input(INPUT)), 336);
+        assert_eq!(Day03::part2(&as_input(INPUT)), 336);
+    }
+
+    #[test]
+    fn test_render() {
+        let map = as_input(INPUT);
+        let rendered = render(&map, Vec2::new(3, 1));
+        assert_eq!(rendered.width(), 33);
+        assert_eq!(rendered.rows().nth(2).map(|row| row.iter().map(|r| r.to_string()).collect::<String>()),
+            Some(".#....X..#..#....#..#..#....#..#.".to_string()));
+        assert_eq!(rendered.cells().iter().filter(|&&r| r == Rendered::Hit).count(), 7);
+        assert_eq!(rendered.cells().iter().filter(|&&r| r == Rendered::Visited).count(), 4);
+
+        let rendered = render(&map, Vec2::new(-1, 2));
+        assert_eq!(rendered.width(), 22);
+        assert_eq!(rendered.to_string().lines().nth(2), Some(".#....#..#O.#....#..#."));
+        assert_eq!(render(&Day03::parse("").unwrap(), Vec2::new(0, 1)).to_string(), "");
+    }
+
+    #[test]
+    fn test_slopes() {
+        let map = as_input(INPUT);
+        // The map repeats to the left as well, 3 left being 8 right on a map 11 wide
+        assert_eq!(num_trees_hit(&map, Vec2::new(-1, 1)), 5);
+        assert_eq!(num_trees_hit(&map, Vec2::new(-4, 1)), 4);
+        assert_eq!(num_trees_hit(&map, Vec2::new(-4, 1)), num_trees_hit(&map, Vec2::new(7, 1)));
+
+        assert_eq!(parse_slopes("1,1 3,1\n# comment\n-1,2 # left").unwrap(), vec![Vec2::new(1, 1), Vec2::new(3, 1), Vec2::new(-1, 2)]);
+        assert_eq!(parse_slopes("1,1\n 3,0").unwrap_err().to_string(), "line 2, column 4: expected positive steps down, found \"0\"");
+        assert!(parse_slopes("3").is_err());
+
+        let ranking = rank_slopes(&map, 5, 5, 1, false);
+        assert_eq!(ranking.len(), 11);
+        assert_eq!(ranking[..3], [(Vec2::new(2, 1), 1), (Vec2::new(-2, 1), 2), (Vec2::new(1, 1), 2)]);
+        assert_eq!(rank_slopes(&map, 5, 5, 1, true)[0], (Vec2::new(3, 1), 7));
     }
 }
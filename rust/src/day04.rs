@@ -1,132 +1,494 @@
-extern crate utils;
-
-use std::env;
-use std::iter::FromIterator;
-use std::collections::HashSet;
 use std::collections::HashMap;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::Solution;
+use crate::parse::ParseError;
+use crate::output::json_string;
 
 type Input = Vec<Passport>;
 
 #[derive(Debug)]
-struct Passport {
-    fields: HashMap<String, String>
+pub struct Passport {
+    /// Line the record starts on.
+    pub line: usize,
+    /// Fields in the order they appear, keys given more than once included.
+    fields: Vec<(String, String)>
 }
 
 impl Passport {
-    fn contain_req_fields(&self, req_fields: &HashSet<&str>) -> bool {
-        let keys = self.fields.keys().map(|k| k.as_str()).collect::<HashSet<_>>();
-        req_fields.difference(&keys).count() == 0
-    }
-    fn req_fields_valid(&self, req_fields: &HashSet<&str>) -> bool {
-        req_fields.iter()
-            .all(|key| {
-                self.fields.get(&key.to_string())
-                    .map(|value| password_field_valid(key, value.as_str()))
-                    .unwrap_or(false)
+    /// Value of the field `key`, the last one given if there are several.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.fields.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Whether every field the schema requires is there, valid or not.
+    pub fn has_required_fields(&self, schema: &Schema) -> bool {
+        schema.fields.iter()
+            .filter(|rule| rule.required)
+            .all(|rule| self.value(&rule.name).is_some())
+    }
+
+    /// Whether every field the schema requires is there and every field it knows is valid.
+    pub fn is_valid(&self, schema: &Schema) -> bool {
+        self.has_required_fields(schema) &&
+            self.fields.iter().all(|(key, _)| {
+                let value = self.value(key).unwrap_or_default();
+                schema.field(key).is_none_or(|rule| rule.check(value).is_ok())
+            })
+    }
+
+    /// What is wrong with the record according to the schema.
+    pub fn diagnose(&self, schema: &Schema) -> Diagnostics {
+        let mut diagnostics = Diagnostics { line: self.line, ..Diagnostics::default() };
+        diagnostics.missing = schema.fields.iter()
+            .filter(|rule| rule.required && self.value(&rule.name).is_none())
+            .map(|rule| rule.name.clone())
+            .collect();
+
+        for (i, (key, value)) in self.fields.iter().enumerate() {
+            // Later values of a key are the ones checked, earlier ones are reported as duplicates
+            if self.fields[(i + 1)..].iter().any(|(k, _)| k == key) {
+                continue;
+            }
+            match schema.field(key).map(|rule| rule.check(value)) {
+                Some(Ok(())) => (),
+                Some(Err(rule)) => diagnostics.invalid.push((key.clone(), value.clone(), rule)),
+                None => diagnostics.unknown.push((key.clone(), value.clone()))
+            }
+        }
+
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (key, value) in &self.fields {
+            match seen.get(key.as_str()) {
+                Some(&idx) => diagnostics.duplicates[idx].1.push(value.clone()),
+                None => {
+                    seen.insert(key, diagnostics.duplicates.len());
+                    diagnostics.duplicates.push((key.clone(), vec![value.clone()]));
+                }
+            }
+        }
+        diagnostics.duplicates.retain(|(_, values)| values.len() > 1);
+
+        diagnostics
+    }
+}
+
+/// Problems of a record, see `Passport::diagnose`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub line: usize,
+    /// Required fields that aren't there.
+    pub missing: Vec<String>,
+    /// Fields, their values and the rules they break.
+    pub invalid: Vec<(String, String, String)>,
+    /// Fields the schema doesn't know and their values.
+    pub unknown: Vec<(String, String)>,
+    /// Keys given more than once and all of their values, the last being the one checked.
+    pub duplicates: Vec<(String, Vec<String>)>
+}
+
+impl Diagnostics {
+    /// Whether the record is valid, unknown fields and duplicate keys being allowed.
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.invalid.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.is_valid() && self.unknown.is_empty() && self.duplicates.is_empty()
+    }
+}
+
+/// Diagnostics of every record with a problem, to triage them.
+pub struct Report {
+    records: usize,
+    complete: usize,
+    valid: usize,
+    /// Number of the record and its diagnostics.
+    problems: Vec<(usize, Diagnostics)>
+}
+
+impl Report {
+    pub fn new(passports: &[Passport], schema: &Schema) -> Report {
+        let diagnostics = passports.iter().map(|p| p.diagnose(schema)).collect::<Vec<_>>();
+        Report {
+            records: passports.len(),
+            complete: passports.iter().filter(|p| p.has_required_fields(schema)).count(),
+            valid: diagnostics.iter().filter(|d| d.is_valid()).count(),
+            problems: diagnostics.into_iter()
+                .enumerate()
+                .filter(|(_, d)| !d.is_empty())
+                .map(|(i, d)| (i + 1, d))
+                .collect()
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("{} records, {} with all required fields, {} valid", self.records, self.complete, self.valid)];
+        for (record, d) in &self.problems {
+            lines.push(String::new());
+            lines.push(format!("Record {} (line {}): {}", record, d.line, if d.is_valid() { "valid" } else { "invalid" }));
+            lines.extend(d.missing.iter().map(|field| format!("  missing {}", field)));
+            lines.extend(d.invalid.iter().map(|(field, value, rule)| format!("  {} = {:?}: {}", field, value, rule)));
+            lines.extend(d.unknown.iter().map(|(field, value)| format!("  unknown {} = {:?}", field, value)));
+            lines.extend(d.duplicates.iter().map(|(field, values)| {
+                format!("  duplicate {}: {}", field, values.iter().map(|v| format!("{:?}", v)).collect::<Vec<_>>().join(", "))
+            }));
+        }
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        let list = |items: Vec<String>| format!("[{}]", items.join(","));
+        let problems = self.problems.iter()
+            .map(|(record, d)| {
+                let missing = d.missing.iter().map(|field| json_string(field)).collect();
+                let invalid = d.invalid.iter()
+                    .map(|(field, value, rule)| format!("{{\"field\":{},\"value\":{},\"rule\":{}}}", json_string(field), json_string(value), json_string(rule)))
+                    .collect();
+                let unknown = d.unknown.iter()
+                    .map(|(field, value)| format!("{{\"field\":{},\"value\":{}}}", json_string(field), json_string(value)))
+                    .collect();
+                let duplicates = d.duplicates.iter()
+                    .map(|(field, values)| format!("{{\"field\":{},\"values\":{}}}", json_string(field), list(values.iter().map(|v| json_string(v)).collect())))
+                    .collect();
+                format!(
+                    "{{\"record\":{},\"line\":{},\"valid\":{},\"missing\":{},\"invalid\":{},\"unknown\":{},\"duplicates\":{}}}",
+                    record, d.line, d.is_valid(), list(missing), list(invalid), list(unknown), list(duplicates)
+                )
             })
+            .collect();
+
+        format!("{{\"records\":{},\"complete\":{},\"valid\":{},\"problems\":{}}}", self.records, self.complete, self.valid, list(problems))
     }
 }
 
-fn password_field_valid(field: &str, value: &str) -> bool {
-    match field {
-        "byr" => {
-            (1920..=2002).contains(&value.parse::<u32>().unwrap())
-        },
-        "iyr" => {
-            (2010..=2020).contains(&value.parse::<u32>().unwrap())
-        },
-        "eyr" => {
-            (2020..=2030).contains(&value.parse::<u32>().unwrap())
-        },
-        "hgt" => {
-            if value.len() >= 3 {
-                let unit = &value[(value.len() - 2)..];
-                let value = value[0..(value.len() - 2)].parse::<u32>().unwrap();
-                match unit {
-                    "cm" => (150..=193).contains(&value),
-                    "in" => (59..=76).contains(&value),
-                    _ => false
+/// The passport fields of the puzzle.
+pub const DEFAULT_SCHEMA: &str =
+"# Passport fields of day 04
+[byr]
+required = true
+type = \"integer\"
+min = 1920
+max = 2002
+
+[iyr]
+required = true
+type = \"integer\"
+min = 2010
+max = 2020
+
+[eyr]
+required = true
+type = \"integer\"
+min = 2020
+max = 2030
+
+[hgt]
+required = true
+type = \"integer\"
+
+[hgt.cm]
+min = 150
+max = 193
+
+[hgt.in]
+min = 59
+max = 76
+
+[hcl]
+required = true
+regex = '^#[0-9a-f]*$'
+
+[ecl]
+required = true
+values = [\"amb\", \"blu\", \"brn\", \"gry\", \"grn\", \"hzl\", \"oth\"]
+
+[pid]
+required = true
+regex = '^[0-9]{9}$'
+
+[cid]
+required = false
+";
+
+lazy_static! {
+    static ref PASSPORT: Schema = Schema::parse(DEFAULT_SCHEMA).expect("Invalid default schema");
+}
+
+/// The fields of records, read from a file like `DEFAULT_SCHEMA` with a section per field:
+///
+/// ```text
+/// [name]
+/// required = true     # whether records must have it, default false
+/// type = "integer"    # or "string", the default
+/// min = 1             # smallest integer allowed, if any
+/// max = 99            # largest integer allowed, if any
+/// regex = '^[a-z]+$'  # pattern strings must match, if any
+/// values = ["a", "b"] # strings allowed, if not any
+///
+/// [name.cm]           # integers must have a unit, cm being one with
+/// min = 10            # its own smallest and largest integer allowed
+/// max = 20
+/// ```
+#[derive(Debug, Default)]
+pub struct Schema {
+    pub fields: Vec<FieldRule>
+}
+
+/// What a field of a record must look like.
+#[derive(Debug)]
+pub struct FieldRule {
+    pub name: String,
+    pub required: bool,
+    pub kind: FieldKind
+}
+
+#[derive(Debug)]
+pub enum FieldKind {
+    Integer { min: Option<i64>, max: Option<i64>, units: Vec<Unit> },
+    String { regex: Option<Regex>, values: Option<Vec<String>> }
+}
+
+/// A unit integers can have, with its own range.
+#[derive(Debug)]
+pub struct Unit {
+    pub name: String,
+    pub min: Option<i64>,
+    pub max: Option<i64>
+}
+
+fn check_range(n: i64, min: Option<i64>, max: Option<i64>) -> Result<(), String> {
+    match (min, max) {
+        (Some(min), _) if n < min => Err(format!("below the minimum of {}", min)),
+        (_, Some(max)) if n > max => Err(format!("above the maximum of {}", max)),
+        _ => Ok(())
+    }
+}
+
+impl FieldRule {
+    /// Checks `value`, describing the rule it breaks if it does.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match &self.kind {
+            FieldKind::Integer { min, max, units } => {
+                let sign = if value.starts_with('-') { 1 } else { 0 };
+                let digits = value[sign..].find(|c: char| !c.is_ascii_digit()).map_or(value.len(), |idx| sign + idx);
+                let (number, unit) = value.split_at(digits);
+                let number = number.parse::<i64>().map_err(|_| "not an integer".to_string())?;
+
+                if units.is_empty() {
+                    return if unit.is_empty() { check_range(number, *min, *max) } else { Err("not an integer".to_string()) };
                 }
-            } else {
-                false
+
+                let names = || units.iter().map(|u| u.name.as_str()).collect::<Vec<_>>().join(" or ");
+                match units.iter().find(|u| u.name == unit) {
+                    Some(u) => check_range(number, *min, *max).and(check_range(number, u.min, u.max)),
+                    None if unit.is_empty() => Err(format!("no unit, expected {}", names())),
+                    None => Err(format!("unit {:?} is not {}", unit, names()))
+                }
+            },
+            FieldKind::String { regex, values } => {
+                if let Some(regex) = regex.as_ref().filter(|r| !r.is_match(value)) {
+                    return Err(format!("doesn't match {}", regex));
+                }
+                match values {
+                    Some(values) if !values.iter().any(|v| v == value) => Err(format!("not one of {}", values.join(", "))),
+                    _ => Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Value of a schema setting.
+enum Setting {
+    Bool(bool),
+    Integer(i64),
+    String(String),
+    List(Vec<String>)
+}
+
+/// Parses a quoted string at the start of `s`, `'...'` as is or `"..."` with `\` escaping
+/// the next character, returning it and what follows.
+fn quoted(s: &str) -> Option<(String, &str)> {
+    let quote = s.chars().next().filter(|&c| c == '"' || c == '\'')?;
+    let mut string = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((idx, chr)) = chars.next() {
+        match chr {
+            c if c == quote => return Some((string, &s[(idx + 1)..])),
+            '\\' if quote == '"' => string.push(chars.next()?.1),
+            c => string.push(c)
+        }
+    }
+    None
+}
+
+/// Parses a setting at the start of `s`, returning it and what follows.
+fn setting(s: &str) -> Option<(Setting, &str)> {
+    if let Some(rest) = s.strip_prefix("true") {
+        Some((Setting::Bool(true), rest))
+    } else if let Some(rest) = s.strip_prefix("false") {
+        Some((Setting::Bool(false), rest))
+    } else if let Some(mut rest) = s.strip_prefix('[') {
+        let mut list = vec![];
+        loop {
+            rest = rest.trim_start();
+            if let Some(rest) = rest.strip_prefix(']') {
+                return Some((Setting::List(list), rest));
             }
-        },
-        "hcl" => {
-            let mut chrs = value.chars();
-            chrs.next() == Some('#') && chrs.all(|chr| chr >= '0' && chr <= '9' || chr >= 'a' && chr <= 'f')
-        },
-        "ecl" => {
-            ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].iter().any(|&ecl| ecl == value)
-        },
-        "pid" => {
-            let mut chrs = value.chars();
-            value.len() == 9 && chrs.all(|chr| chr >= '0' && chr <= '9')
-        },
-        _ => unreachable!()
-    }
-}
-
-fn solve(input: &Input) -> (usize, usize) {
-    let req_fields = HashSet::from_iter(["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"].iter().cloned());
-
-    input.iter()
-        .fold((0, 0), |(p1, p2), passport| {
-            (
-                p1 + if passport.contain_req_fields(&req_fields) { 1 } else { 0 },
-                p2 + if passport.req_fields_valid(&req_fields) { 1 } else { 0 }
-            )
-        })
-}
-
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        let (part1, part2) = solve(&input);
-        println!("Part1: {}", part1);
-        println!("Part2: {}", part2);
-    });
-}
-
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    let mut passports = vec![];
-    let mut curr_fields: HashMap<String, String> = HashMap::new();
-
-    for line in reader.lines().map(|l| l.unwrap()) {
-        let line = line.as_str();
-
-        if line.trim().len() == 0 {
-            let mut fields: HashMap<String, String> = HashMap::new();
-            std::mem::swap(&mut curr_fields, &mut fields);
-            passports.push(Passport { fields });
-        } else {
-            for valuepair in line.split_ascii_whitespace() {
-                let mut iter = valuepair.split(':');
-                let key = iter.next().unwrap().to_string();
-                let val = iter.next().unwrap().to_string();
-                curr_fields.insert(key, val);
+            let (string, after) = quoted(rest)?;
+            list.push(string);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return None;
             }
         }
+    } else if let Some((string, rest)) = quoted(s) {
+        Some((Setting::String(string), rest))
+    } else {
+        let len = s.find(|c: char| !(c.is_ascii_digit() || c == '-')).unwrap_or(s.len());
+        Some((Setting::Integer(s[..len].parse().ok()?), &s[len..]))
     }
+}
 
-    if curr_fields.len() > 0 {
-        let mut fields: HashMap<String, String> = HashMap::new();
-        std::mem::swap(&mut curr_fields, &mut fields);
-        passports.push(Passport { fields });
+impl Schema {
+    pub fn field(&self, name: &str) -> Option<&FieldRule> {
+        self.fields.iter().find(|rule| rule.name == name)
     }
 
-    Ok(passports)
+    pub fn parse(input: &str) -> Result<Schema, ParseError> {
+        let mut fields: Vec<FieldRule> = vec![];
+        // Index of the field and unit of the current section
+        let mut section: Option<(usize, Option<usize>)> = None;
+
+        for (i, line) in input.lines().enumerate() {
+            let at = |token: &str, expected: &str| ParseError::at(line, token, expected).with_line(i + 1);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.strip_suffix(']').ok_or_else(|| at(line, "'[field]' or '[field.unit]'"))?;
+                let mut names = header.splitn(2, '.');
+                let name = names.next().unwrap_or(header);
+                let field = fields.iter().position(|rule| rule.name == name);
+
+                section = match (field, names.next()) {
+                    (None, None) => {
+                        fields.push(FieldRule { name: name.to_string(), required: false, kind: FieldKind::String { regex: None, values: None } });
+                        Some((fields.len() - 1, None))
+                    },
+                    (Some(_), None) => return Err(at(name, "field not defined before")),
+                    (None, Some(_)) => return Err(at(name, "field defined before its units")),
+                    (Some(field), Some(unit)) => match &mut fields[field].kind {
+                        FieldKind::Integer { units, .. } if !units.iter().any(|u| u.name == unit) => {
+                            units.push(Unit { name: unit.to_string(), min: None, max: None });
+                            Some((field, Some(units.len() - 1)))
+                        },
+                        FieldKind::Integer { .. } => return Err(at(unit, "unit not defined before")),
+                        FieldKind::String { .. } => return Err(at(unit, "unit of a field with type = \"integer\""))
+                    }
+                };
+                continue;
+            }
+
+            let (field, unit) = section.ok_or_else(|| at(line, "'[field]' before settings"))?;
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or(line).trim();
+            let value = parts.next().map(|v| v.trim_start()).ok_or_else(|| at(&line[line.len()..], "'='"))?;
+            let (setting, rest) = setting(value).ok_or_else(|| at(value, "true, false, integer, quoted string or list of them"))?;
+            let value = &value[..(value.len() - rest.len())];
+            let rest = rest.trim();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                return Err(at(rest, "end of line"));
+            }
+
+            let rule = &mut fields[field];
+            match (key, setting, &mut rule.kind, unit) {
+                ("min", Setting::Integer(n), FieldKind::Integer { units, .. }, Some(u)) => units[u].min = Some(n),
+                ("max", Setting::Integer(n), FieldKind::Integer { units, .. }, Some(u)) => units[u].max = Some(n),
+                (_, _, _, Some(_)) => return Err(at(key, "'min' or 'max' integer of the unit")),
+                ("required", Setting::Bool(b), _, _) => rule.required = b,
+                ("type", Setting::String(t), kind, _) => *kind = match t.as_str() {
+                    "integer" => FieldKind::Integer { min: None, max: None, units: vec![] },
+                    "string" => FieldKind::String { regex: None, values: None },
+                    _ => return Err(at(value, "type \"integer\" or \"string\""))
+                },
+                ("min", Setting::Integer(n), FieldKind::Integer { min, .. }, _) => *min = Some(n),
+                ("max", Setting::Integer(n), FieldKind::Integer { max, .. }, _) => *max = Some(n),
+                ("regex", Setting::String(pattern), FieldKind::String { regex, .. }, _) =>
+                    *regex = Some(Regex::new(&pattern).map_err(|_| at(value, "valid regular expression"))?),
+                ("values", Setting::List(list), FieldKind::String { values, .. }, _) => *values = Some(list),
+                (_, _, FieldKind::Integer { .. }, _) => return Err(at(key, "'required', 'type', 'min' or 'max' setting of an integer field")),
+                (_, _, FieldKind::String { .. }, _) => return Err(at(key, "'required', 'type', 'regex' or 'values' setting of a string field"))
+            }
+        }
+
+        Ok(Schema { fields })
+    }
 }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+pub struct Day04;
+
+impl Solution for Day04 {
+    const DAY: u32 = 4;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        let mut passports = vec![];
+        let mut curr_fields: Vec<(String, String)> = vec![];
+        let mut curr_line = 1;
+
+        for (i, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                // Several blank lines in a row still separate just two passports
+                if !curr_fields.is_empty() {
+                    passports.push(Passport { line: curr_line, fields: std::mem::take(&mut curr_fields) });
+                }
+            } else {
+                if curr_fields.is_empty() {
+                    curr_line = i + 1;
+                }
+                for valuepair in line.split_ascii_whitespace() {
+                    let mut iter = valuepair.splitn(2, ':');
+                    let key = iter.next().filter(|key| !key.is_empty())
+                        .ok_or_else(|| ParseError::at(line, valuepair, "key before ':'").with_line(i + 1))?
+                        .to_string();
+                    let val = iter.next()
+                        .ok_or_else(|| ParseError::at(line, valuepair, "key:value pair").with_line(i + 1))?
+                        .to_string();
+                    curr_fields.push((key, val));
+                }
+            }
+        }
+
+        if !curr_fields.is_empty() {
+            passports.push(Passport { line: curr_line, fields: curr_fields });
+        }
+
+        Ok(passports)
+    }
+
+    fn part1(input: &Input) -> usize {
+        input.iter()
+            .filter(|passport| passport.has_required_fields(&PASSPORT))
+            .count()
+    }
+
+    fn part2(input: &Input) -> usize {
+        input.iter()
+            .filter(|passport| passport.is_valid(&PASSPORT))
+            .count()
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes, clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -175,18 +537,32 @@ mod tests {
         iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day04::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(solve(&as_input(INPUT_P1)).0, 2);
+        assert_eq!(Day04::part1(&as_input(INPUT_P1)), 2);
+    }
+
+    #[test]
+    fn test_parse_blank_lines() {
+        let passports = as_input("\n\nbyr:1980 iyr:2012\n\n\n\neyr:2030\nhgt:74in\n\n");
+        assert_eq!(passports.iter().map(|p| p.line).collect::<Vec<_>>(), vec![3, 7]);
+        assert_eq!(passports[1].fields, vec![("eyr".to_string(), "2030".to_string()), ("hgt".to_string(), "74in".to_string())]);
+        assert!(as_input("\n \n").is_empty());
+    }
+
+    fn password_field_valid(field: &str, value: &str) -> bool {
+        PASSPORT.field(field).unwrap().check(value).is_ok()
     }
 
     #[test]
     fn test_password_field_valid() {
         assert_eq!(password_field_valid("byr", "2002"), true);
         assert_eq!(password_field_valid("byr", "2003"), false);
+        assert_eq!(password_field_valid("byr", "abc"), false);
+        assert_eq!(password_field_valid("byr", "+2000"), false);
 
         assert_eq!(password_field_valid("hgt", "60in"), true);
         assert_eq!(password_field_valid("hgt", "190cm"), true);
@@ -204,9 +580,96 @@ mod tests {
         assert_eq!(password_field_valid("pid", "0123456789"), false);
     }
 
+    #[test]
+    fn test_schema() {
+        let schema = Schema::parse(
+           "[id]
+            required = true
+            regex = '^[A-Z]+-[0-9]+$' # like ABC-123
+            [count]
+            type = \"integer\"
+            min = 0
+            [size]
+            type = \"integer\"
+            [size.kg]
+            max = 10
+            [size.t]").unwrap();
+        let check = |field: &str, value: &str| schema.field(field).unwrap().check(value);
+        assert_eq!(check("id", "AB-12"), Ok(()));
+        assert_eq!(check("id", "ab-12"), Err("doesn't match ^[A-Z]+-[0-9]+$".to_string()));
+        assert_eq!(check("count", "12x"), Err("not an integer".to_string()));
+        assert_eq!(check("size", "11kg"), Err("above the maximum of 10".to_string()));
+        assert_eq!(check("size", "11t"), Ok(()));
+        assert_eq!(check("size", "11"), Err("no unit, expected kg or t".to_string()));
+        assert_eq!(check("size", "-3kg"), Ok(()));
+        assert_eq!(check("count", "-1"), Err("below the minimum of 0".to_string()));
+        assert_eq!(check("count", "-"), Err("not an integer".to_string()));
+        assert_eq!(check("count", "1-2"), Err("not an integer".to_string()));
+        assert_eq!(PASSPORT.field("ecl").unwrap().check("xyz"), Err("not one of amb, blu, brn, gry, grn, hzl, oth".to_string()));
+
+        let passports = as_input("id:A-1 count:3\n\ncount:-1 id:B-2 other:x\n\ncount:1");
+        assert_eq!(passports.iter().map(|p| (p.has_required_fields(&schema), p.is_valid(&schema))).collect::<Vec<_>>(),
+            vec![(true, true), (true, false), (false, false)]);
+
+        let error = |schema: &str| Schema::parse(schema).unwrap_err().to_string();
+        assert_eq!(error("min = 1"), "line 1, column 1: expected '[field]' before settings, found \"min = 1\"");
+        assert_eq!(error("[a]\nmin = 1"), "line 2, column 1: expected 'required', 'type', 'regex' or 'values' setting of a string field, found \"min\"");
+        assert_eq!(error("[a]\ntype = 'float' # no"), "line 2, column 8: expected type \"integer\" or \"string\", found \"'float'\"");
+        assert!(Schema::parse("[a]\nregex = '('").is_err());
+        assert!(Schema::parse("[a.cm]").is_err());
+        assert!(Schema::parse("[a]\n[a]").is_err());
+        assert!(Schema::parse("[a]\nrequired = yes").is_err());
+        assert!(Schema::parse("[a]\nvalues = [\"x\" 1]").is_err());
+
+        let schema = Schema::parse("[t]\ntype = \"integer\"\nmin = -10\nmax = 10\n[at]\nregex = '^[0-9]{2}:[0-9]{2}$'").unwrap();
+        let passports = as_input("t:-5 at:12:30\n\nt:-11 at:12");
+        assert_eq!(passports[0].value("at"), Some("12:30"));
+        assert!(passports[0].is_valid(&schema));
+        assert_eq!(passports[1].diagnose(&schema).invalid.iter().map(|(field, _, rule)| format!("{}: {}", field, rule)).collect::<Vec<_>>(),
+            vec!["t: below the minimum of -10", "at: doesn't match ^[0-9]{2}:[0-9]{2}$"]);
+        assert_eq!(Day04::parse("t:1\n:5").unwrap_err().to_string(), "line 2, column 1: expected key before ':', found \":5\"");
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let passports = as_input(&format!("{}\n\nbyr:19x iyr:2012 foo:x iyr:2013\necl:blu iyr:2010", INPUT_P1));
+        assert_eq!(passports.iter().map(|p| p.line).collect::<Vec<_>>(), vec![1, 4, 7, 12, 15]);
+        assert!(passports.iter().take(4).all(|p| p.diagnose(&PASSPORT).invalid.is_empty()));
+        assert_eq!(passports[1].diagnose(&PASSPORT).missing, vec!["hgt"]);
+
+        let diagnostics = passports[4].diagnose(&PASSPORT);
+        assert_eq!(diagnostics, Diagnostics {
+            line: 15,
+            missing: ["eyr", "hgt", "hcl", "pid"].iter().map(|f| f.to_string()).collect(),
+            invalid: vec![("byr".to_string(), "19x".to_string(), "not an integer".to_string())],
+            unknown: vec![("foo".to_string(), "x".to_string())],
+            duplicates: vec![("iyr".to_string(), vec!["2012".to_string(), "2013".to_string(), "2010".to_string()])]
+        });
+        assert!(!diagnostics.is_valid());
+
+        let report = Report::new(&passports[3..], &PASSPORT);
+        assert_eq!(report.to_text(), "\
+2 records, 0 with all required fields, 0 valid
+
+Record 1 (line 12): invalid
+  missing byr
+
+Record 2 (line 15): invalid
+  missing eyr
+  missing hgt
+  missing hcl
+  missing pid
+  byr = \"19x\": not an integer
+  unknown foo = \"x\"
+  duplicate iyr: \"2012\", \"2013\", \"2010\"");
+        assert_eq!(Report::new(&passports[4..], &Schema::parse("[byr]\n[iyr]\n[foo]\n[ecl]").unwrap()).to_json(),
+            "{\"records\":1,\"complete\":1,\"valid\":1,\"problems\":[{\"record\":1,\"line\":15,\"valid\":true,\"missing\":[],\"invalid\":[],\"unknown\":[],\
+             \"duplicates\":[{\"field\":\"iyr\",\"values\":[\"2012\",\"2013\",\"2010\"]}]}]}");
+    }
+
     #[test]
     fn test_part2() {
-        assert_eq!(solve(&as_input(INPUT_P2_INVALID)).1, 0);
-        assert_eq!(solve(&as_input(INPUT_P2_VALID)).1, 4);
+        assert_eq!(Day04::part2(&as_input(INPUT_P2_INVALID)), 0);
+        assert_eq!(Day04::part2(&as_input(INPUT_P2_VALID)), 4);
     }
 }
@@ -1,12 +1,7 @@
-extern crate utils;
-
-use std::env;
 use std::collections::BTreeSet;
 use std::ops::RangeInclusive;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::parse::{ParseError, parse_lines};
 
 type Seat = String;
 
@@ -37,44 +32,58 @@ fn seat_id(seat: &str) -> usize {
     row * 8 + col
 }
 
-fn part1(input: &Input) -> usize {
-    input.iter()
-        .map(|seat| seat_id(seat.as_str())).max()
-        .unwrap_or(0)
-}
-
-fn part2(input: &Input) -> usize {
-    let seat_ids: BTreeSet<_> = input.iter()
-        .map(|seat| seat_id(seat.as_str()))
-        .collect();
-
-    seat_ids.iter()
-        .find(|&seat_id| {
-            let check_id = seat_id + 1;
-            seat_ids.get(&check_id).is_none() && seat_ids.get(&(check_id + 1)).is_some()
+pub struct Day05;
+
+impl Solution for Day05 {
+    const DAY: u32 = 5;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, |line| {
+            let mut chrs = line.char_indices();
+
+            for &(n, l_chr, h_chr, expected) in &[(7, 'F', 'B', "'F' or 'B'"), (3, 'L', 'R', "'L' or 'R'")] {
+                for _ in 0..n {
+                    match chrs.next() {
+                        Some((_, c)) if c == l_chr || c == h_chr => {},
+                        Some((i, c)) => return Err(ParseError::at(line, &line[i..(i + c.len_utf8())], expected)),
+                        None => return Err(ParseError::at_end(line, expected))
+                    }
+                }
+            }
+            if let Some((i, _)) = chrs.next() {
+                return Err(ParseError::at(line, &line[i..], "end of line"));
+            }
+
+            Ok(line.to_string())
         })
-        .map(|seat_id| seat_id + 1)
-        .unwrap_or( 0)
-}
-
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        println!("Part1: {}", part1(&input));
-        println!("Part2: {}", part2(&input));
-    });
-}
+    }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap()).collect())
-}
+    fn part1(input: &Input) -> usize {
+        input.iter()
+            .map(|seat| seat_id(seat.as_str())).max()
+            .unwrap_or(0)
+    }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+    fn part2(input: &Input) -> usize {
+        let seat_ids: BTreeSet<_> = input.iter()
+            .map(|seat| seat_id(seat.as_str()))
+            .collect();
+
+        seat_ids.iter()
+            .find(|&seat_id| {
+                let check_id = seat_id + 1;
+                !seat_ids.contains(&check_id) && seat_ids.contains(&(check_id + 1))
+            })
+            .map(|seat_id| seat_id + 1)
+            .unwrap_or( 0)
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -85,7 +94,7 @@ mod tests {
         BBFFBBFRLL";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day05::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
@@ -97,6 +106,6 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&as_input(INPUT)), 820);
+        assert_eq!(Day05::part1(&as_input(INPUT)), 820);
     }
 }
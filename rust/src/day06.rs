@@ -1,75 +1,70 @@
-extern crate utils;
-
-use std::env;
 use std::collections::HashMap;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::parse::ParseError;
 
 type Input = Vec<Answers>;
 
 #[derive(Debug)]
-struct Answers {
+pub struct Answers {
     n_people: usize,
     freq: HashMap<char, usize>
 }
 
-fn solve(input: &Input) -> (usize, usize) {
-    input.iter()
-        .fold((0, 0), |(p1, p2), a| {
-            (
-                p1 + a.freq.keys().len(),
-                p2 + a.freq.values().filter(|&&f| f == a.n_people).count()
-            )
-        })
-}
-
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        let (part1, part2) = solve(&input);
-        println!("Part1: {}", part1);
-        println!("Part2: {}", part2);
-    });
-}
-
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    let mut answers = vec![];
-    let mut curr_freq: HashMap<char, usize> = HashMap::new();
-    let mut n_people = 0;
-
-    for line in reader.lines().map(|l| l.unwrap()) {
-        let line = line.as_str();
+pub struct Day06;
+
+impl Solution for Day06 {
+    const DAY: u32 = 6;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        let mut answers = vec![];
+        let mut curr_freq: HashMap<char, usize> = HashMap::new();
+        let mut n_people = 0;
+
+        for (i, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                let mut freq: HashMap<char, usize> = HashMap::new();
+                std::mem::swap(&mut curr_freq, &mut freq);
+                answers.push(Answers { n_people, freq });
+                n_people = 0;
+            } else {
+                n_people += 1;
+                for (j, chr) in line.char_indices() {
+                    if !chr.is_ascii_lowercase() {
+                        let token = &line[j..(j + chr.len_utf8())];
+                        return Err(ParseError::at(line, token, "answer 'a'-'z'").with_line(i + 1));
+                    }
+                    *curr_freq.entry(chr).or_default() += 1;
+                }
+            }
+        }
 
-        if line.trim().len() == 0 {
+        if !curr_freq.is_empty() {
             let mut freq: HashMap<char, usize> = HashMap::new();
             std::mem::swap(&mut curr_freq, &mut freq);
             answers.push(Answers { n_people, freq });
-            n_people = 0;
-        } else {
-            n_people += 1;
-            for chr in line.chars() {
-                *curr_freq.entry(chr).or_default() += 1;
-            }
         }
-    }
 
-    if curr_freq.len() > 0 {
-        let mut freq: HashMap<char, usize> = HashMap::new();
-        std::mem::swap(&mut curr_freq, &mut freq);
-        answers.push(Answers { n_people, freq });
+        Ok(answers)
     }
 
-    Ok(answers)
-}
+    fn part1(input: &Input) -> usize {
+        input.iter()
+            .map(|a| a.freq.keys().len())
+            .sum()
+    }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+    fn part2(input: &Input) -> usize {
+        input.iter()
+            .map(|a| a.freq.values().filter(|&&f| f == a.n_people).count())
+            .sum()
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -91,16 +86,16 @@ mod tests {
         b";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day06::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(solve(&as_input(INPUT)).0, 11);
+        assert_eq!(Day06::part1(&as_input(INPUT)), 11);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(solve(&as_input(INPUT)).1, 6);
+        assert_eq!(Day06::part2(&as_input(INPUT)), 6);
     }
 }
@@ -1,24 +1,15 @@
-extern crate regex;
-#[macro_use] extern crate lazy_static;
-extern crate utils;
-
-use std::env;
 use std::iter::FromIterator;
 use std::collections::HashSet;
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::str::FromStr;
-use std::num::ParseIntError;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use regex::Regex;
-use utils::*;
+use crate::Solution;
+use crate::parse::{ParseError, parse_lines, parse_token, expect_token};
 
 type Input = Vec<BagRule>;
 
 #[derive(Debug)]
-struct BagRule {
+pub struct BagRule {
     bag: String,
     contains: HashMap<String, u32>
 }
@@ -56,34 +47,48 @@ fn solve(input: &Input) -> (usize, u32) {
     )
 }
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        let (part1, part2) = solve(&input);
-        println!("Part1: {}", part1);
-        println!("Part2: {}", part2);
-    });
+pub struct Day07;
+
+impl Solution for Day07 {
+    const DAY: u32 = 7;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = u32;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, str::parse::<BagRule>)
+    }
+
+    fn part1(input: &Input) -> usize {
+        solve(input).0
+    }
+
+    fn part2(input: &Input) -> u32 {
+        solve(input).1
+    }
 }
 
 impl FromStr for BagRule {
-    type Err = ParseIntError;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^(.+ .+) bags contain (.+).$").unwrap();
-        }
-        let caps = RE.captures(s).unwrap();
+        let mut parts = s.splitn(2, " bags contain ");
+        let bag = parts.next().unwrap_or(s).to_string();
+        let contents = expect_token(s, parts.next(), "' bags contain '")?;
+        let contents = contents.strip_suffix('.').ok_or_else(|| ParseError::at_end(s, "'.'"))?;
 
-        let bag = caps[1].to_string();
         let mut contains: HashMap<String, u32> = HashMap::new();
 
-        for contain in caps[2].split(", ") {
-            let mut parts = contain.split_ascii_whitespace();
-
-            if let Some(first) = parts.next() {
-                if let Ok(cnt) = first.parse::<u32>() {
-                    let bag = parts.take(2).collect::<Vec<_>>().join(" ");
-                    contains.insert(bag, cnt);
+        if contents != "no other bags" {
+            for contain in contents.split(", ") {
+                let mut parts = contain.split(' ');
+                let cnt = parse_token(s, parts.next(), "bag count or 'no other bags'")?;
+                let adjective = expect_token(s, parts.next(), "bag adjective")?;
+                let color = expect_token(s, parts.next(), "bag color")?;
+                let unit = expect_token(s, parts.next(), "'bag' or 'bags'")?;
+                if unit != "bag" && unit != "bags" {
+                    return Err(ParseError::at(s, unit, "'bag' or 'bags'"));
                 }
+                contains.insert(format!("{} {}", adjective, color), cnt);
             }
         }
 
@@ -94,16 +99,8 @@ impl FromStr for BagRule {
     }
 }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap().parse::<BagRule>().unwrap()).collect())
-}
-
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
-}
-
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -128,17 +125,25 @@ mod tests {
         dark violet bags contain no other bags.";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day07::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(solve(&as_input(INPUT)).0, 4);
+        assert_eq!(Day07::part1(&as_input(INPUT)), 4);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(solve(&as_input(INPUT)).1, 32);
-        assert_eq!(solve(&as_input(INPUT2)).1, 126);
+        assert_eq!(Day07::part2(&as_input(INPUT)), 32);
+        assert_eq!(Day07::part2(&as_input(INPUT2)), 126);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = |s: &str| Day07::parse(s).unwrap_err().to_string();
+        assert_eq!(error("faded blue bags contain no other bags.\nlight red bags contain 1 bright white bag"), "line 2, column 42: expected '.', found end of line");
+        assert_eq!(error("light red bags contain two bright white bags."), "line 1, column 24: expected bag count or 'no other bags', found \"two\"");
+        assert_eq!(error("light red bags contain 1 bright white boxes."), "line 1, column 39: expected 'bag' or 'bags', found \"boxes\"");
     }
 }
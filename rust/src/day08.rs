@@ -1,13 +1,10 @@
-extern crate utils;
-
-use std::env;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::str::FromStr;
-use std::num::ParseIntError;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::debugger::Debugger;
+use crate::vm::{self, Flow, Machine, Status, Trace};
+use crate::parse::{ParseError, expect_token, parse_lines, parse_token};
 
 type Input = Vec<Instruction>;
 
@@ -16,100 +13,449 @@ enum OpCode {
     Nop, Acc, Jmp
 }
 
-#[derive(Copy, Clone, Debug)]
-struct Instruction {
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct Instruction {
     opcode: OpCode,
     arg: i32
 }
-#[derive(Debug)]
-struct Console <'a> {
-    instructions: &'a Vec<Instruction>,
-    pc: usize,
-    accumulator: i32
+
+impl vm::Instruction for Instruction {
+    fn execute(&self, registers: &mut [i64]) -> Flow {
+        match self.opcode {
+            OpCode::Nop => Flow::Next,
+            OpCode::Acc => {
+                registers[0] = registers[0].wrapping_add(self.arg as i64);
+                Flow::Next
+            },
+            OpCode::Jmp => Flow::Jump(self.arg as isize)
+        }
+    }
+}
+
+/// Names of the console's registers.
+pub const REGISTERS: &[&str] = &["acc"];
+
+/// The handheld console, with its accumulator as the only register.
+fn console(program: &[Instruction]) -> Machine<'_, Instruction> {
+    Machine::new(program, 1)
+}
+
+/// Interactive debugger for the console running `program`.
+pub fn debugger(program: &[Instruction]) -> Debugger<'_, Instruction> {
+    Debugger::new(console(program), REGISTERS)
+}
+
+/// Runs `program` on the console until it ends or an instruction is about to run a second time.
+pub fn trace(program: &[Instruction]) -> Trace {
+    console(program).trace()
+}
+
+pub struct Day08;
+
+impl Solution for Day08 {
+    const DAY: u32 = 8;
+    type Input = Input;
+    type Part1 = i64;
+    type Part2 = Repair;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, str::parse::<Instruction>)
+    }
+
+    fn part1(input: &Input) -> i64 {
+        let mut console = console(input);
+        console.run();
+        console.registers[0]
+    }
+
+    fn part2(input: &Input) -> Repair {
+        repair(input)
+    }
 }
 
-impl<'a> Console<'a> {
-    fn from_program(instructions: &'a Vec<Instruction>) -> Self {
-        Console { instructions, pc: 0, accumulator: 0 }
+impl Instruction {
+    /// The instruction with `jmp` and `nop` swapped, if it is one of them.
+    fn flipped(self) -> Option<Instruction> {
+        match self.opcode {
+            OpCode::Jmp => Some(Instruction { opcode: OpCode::Nop, ..self }),
+            OpCode::Nop => Some(Instruction { opcode: OpCode::Jmp, ..self }),
+            OpCode::Acc => None
+        }
     }
 
-    fn step(&mut self) {
-        let Instruction { opcode, arg } = self.instructions[self.pc];
+    /// Offset to the next instruction to run.
+    fn offset(self) -> isize {
+        match self.opcode {
+            OpCode::Jmp => self.arg as isize,
+            _ => 1
+        }
+    }
+}
 
-        match opcode {
-            OpCode::Nop => {
-                self.pc += 1;
-            },
-            OpCode::Acc => {
-                self.accumulator += arg;
-                self.pc += 1;
-            },
-            OpCode::Jmp => {
-                self.pc += arg as usize;
+/// Outcome of looking for the instruction to flip between `jmp` and `nop` to make a program terminate.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Repair {
+    /// Flipping the instruction at `pc` makes the program terminate with the accumulator at `acc`.
+    Flip { pc: usize, acc: i64 },
+    /// The program terminates as it is, with the accumulator at `acc`.
+    NotNeeded { acc: i64 },
+    /// No single flip makes the program terminate.
+    Impossible,
+    /// Several flips make the program terminate, each as `Flip`.
+    Ambiguous(Vec<Repair>)
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repair::Flip { acc, .. } => write!(f, "{}", acc),
+            Repair::NotNeeded { acc } => write!(f, "{} (no repair needed)", acc),
+            Repair::Impossible => write!(f, "no repair"),
+            Repair::Ambiguous(repairs) => {
+                let flips = repairs.iter()
+                    .filter_map(|r| match r {
+                        Repair::Flip { pc, acc } => Some(format!("pc {} gives {}", pc, acc)),
+                        _ => None
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "{} possible repairs: {}", flips.len(), flips.join(", "))
             }
         }
     }
+}
 
-    fn run_until_loop_or_end(&mut self) {
-        let mut visited = HashSet::new();
+/// For each pc, and the end at `program.len()`, how much running from there adds to the
+/// accumulator if it terminates. Found by walking the instruction graph backwards from
+/// the end, each instruction having a single successor.
+fn terminating(program: &[Instruction]) -> Vec<Option<i64>> {
+    let len = program.len();
+    let mut predecessors = vec![vec![]; len + 1];
+    for (pc, instruction) in program.iter().enumerate() {
+        let next = pc as isize + instruction.offset();
+        if (0..=len as isize).contains(&next) {
+            predecessors[next as usize].push(pc);
+        }
+    }
 
-        loop {
-            if visited.contains(&self.pc) || self.pc >= self.instructions.len() {
-                break;
+    let mut terminates: Vec<Option<i64>> = vec![None; len + 1];
+    terminates[len] = Some(0);
+    let mut stack = vec![len];
+    while let Some(pc) = stack.pop() {
+        let delta = terminates[pc].unwrap_or_default();
+        for &prev in &predecessors[pc] {
+            if terminates[prev].is_none() {
+                let acc = if program[prev].opcode == OpCode::Acc { program[prev].arg as i64 } else { 0 };
+                terminates[prev] = Some(delta.wrapping_add(acc));
+                stack.push(prev);
             }
+        }
+    }
+    terminates
+}
 
-            visited.insert(self.pc);
-            self.step();
+/// Finds the flips between `jmp` and `nop` that make `program` terminate in linear time.
+///
+/// Only a flip of an instruction the program runs can change what it does. Running it
+/// until it loops, a flip terminates exactly when the flipped instruction leads to a pc
+/// that terminates unmodified, as none of the instructions run before, the flipped one
+/// included, can be reached from there. So the accumulator it ends with is the one at the
+/// flip plus what running from that pc adds, without running the program again.
+pub fn repair(program: &[Instruction]) -> Repair {
+    let terminates = terminating(program);
+    let mut visited = vec![false; program.len()];
+    let mut flips = vec![];
+
+    let mut machine = console(program);
+    while machine.status().is_none() {
+        let pc = machine.pc as usize;
+        if std::mem::replace(&mut visited[pc], true) {
+            break;
+        }
+
+        if let Some(flipped) = program[pc].flipped() {
+            let next = pc as isize + flipped.offset();
+            let delta = if (0..=program.len() as isize).contains(&next) { terminates[next as usize] } else { None };
+            if let Some(delta) = delta {
+                flips.push(Repair::Flip { pc, acc: machine.registers[0].wrapping_add(delta) });
+            }
         }
+        machine.step();
+    }
+
+    if machine.status() == Some(Status::Terminated) {
+        return Repair::NotNeeded { acc: machine.registers[0] };
+    }
+
+    match flips.len() {
+        0 => Repair::Impossible,
+        1 => flips.remove(0),
+        _ => Repair::Ambiguous(flips)
     }
 }
 
-fn part1(input: &Input) -> i32 {
-    let mut console = Console::from_program(input);
-    console.run_until_loop_or_end();
-    console.accumulator
+/// Where control goes from an instruction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Target {
+    Pc(usize),
+    /// Right after the last instruction, where the program terminates.
+    End,
+    OutOfBounds(isize)
 }
 
-fn part2(input: &Input) -> i32 {
-    fn swap_opcode(opcode: OpCode) -> Option<OpCode> {
-        match opcode {
-            OpCode::Jmp => Some(OpCode::Nop),
-            OpCode::Nop => Some(OpCode::Jmp),
-            _ => None
+impl Target {
+    fn of(program: &[Instruction], pc: isize) -> Target {
+        match pc {
+            pc if pc == program.len() as isize => Target::End,
+            pc if pc >= 0 && pc < program.len() as isize => Target::Pc(pc as usize),
+            pc => Target::OutOfBounds(pc)
         }
     }
+}
 
-    let mut program = input.clone();
+/// A run of instructions only entered at its first and only left after its last.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BasicBlock {
+    pub start: usize,
+    /// One past the last instruction.
+    pub end: usize,
+    /// Where the block goes next, the target of its `jmp` if it ends in one.
+    pub next: Target,
+    /// Whether the program gets here when run from the start.
+    pub reachable: bool,
+    /// Whether the block is part of the loop the program runs into.
+    pub in_loop: bool
+}
 
-    for i in 0..input.len() {
-        let old_instruction = program[i];
+/// Control-flow graph of a console program, with the path it takes when run from the start.
+pub struct ControlFlow<'a> {
+    program: &'a [Instruction],
+    pub blocks: Vec<BasicBlock>,
+    /// The pcs of the `jmp` instructions targeting each pc.
+    pub jumps_to: Vec<Vec<usize>>,
+    pub trace: Trace
+}
 
-        let Instruction { opcode, arg } = old_instruction;
+impl<'a> ControlFlow<'a> {
+    pub fn new(program: &'a [Instruction]) -> ControlFlow<'a> {
+        let trace = trace(program);
+        let mut reachable = vec![false; program.len()];
+        for step in &trace.steps {
+            reachable[step.pc] = true;
+        }
+        let mut in_loop = vec![false; program.len()];
+        for &pc in &trace.loop_body {
+            in_loop[pc] = true;
+        }
 
-        if let Some(opcode) = swap_opcode(opcode) {
-            program[i] = Instruction { opcode, arg };
-        } else {
-            continue;
+        // Blocks start at the first instruction, jump targets and right after jumps
+        let mut jumps_to = vec![vec![]; program.len()];
+        let mut leaders = vec![false; program.len() + 1];
+        leaders[0] = true;
+        for (pc, instruction) in program.iter().enumerate().filter(|(_, i)| i.opcode == OpCode::Jmp) {
+            if let Target::Pc(target) = Target::of(program, pc as isize + instruction.offset()) {
+                jumps_to[target].push(pc);
+                leaders[target] = true;
+            }
+            leaders[pc + 1] = true;
+        }
+
+        let starts = (0..program.len()).filter(|&pc| leaders[pc]).collect::<Vec<_>>();
+        let blocks = starts.iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(program.len());
+                let last = end - 1;
+                BasicBlock {
+                    start,
+                    end,
+                    next: Target::of(program, last as isize + program[last].offset()),
+                    reachable: reachable[start],
+                    in_loop: in_loop[start]
+                }
+            })
+            .collect();
+
+        ControlFlow { program, blocks, jumps_to, trace }
+    }
+
+    /// The program one instruction per line, annotated with jumps, unreachable code and the loop.
+    pub fn disassemble(&self) -> String {
+        let target = |target: Target| match target {
+            Target::Pc(pc) => pc.to_string(),
+            Target::End => "end".to_string(),
+            Target::OutOfBounds(pc) => format!("{} (out of bounds)", pc)
+        };
+
+        let mut lines = vec![];
+        for block in &self.blocks {
+            if block.start > 0 {
+                lines.push(String::new());
+            }
+            for pc in block.start..block.end {
+                let instruction = self.program[pc];
+                let mut notes = vec![];
+                if self.trace.loop_entry == Some(pc) {
+                    notes.push("loop entry".to_string());
+                }
+                if !self.jumps_to[pc].is_empty() {
+                    notes.push(format!("<- {}", self.jumps_to[pc].iter().map(|pc| pc.to_string()).collect::<Vec<_>>().join(", ")));
+                }
+                if instruction.opcode == OpCode::Jmp {
+                    notes.push(format!("-> {}", target(block.next)));
+                }
+                if !block.reachable {
+                    notes.push("unreachable".to_string());
+                }
+
+                let marker = if block.in_loop { "*" } else { " " };
+                let line = format!("{} {:>5}  {}", marker, pc, instruction);
+                lines.push(if notes.is_empty() { line } else { format!("{:<20} ; {}", line, notes.join(", ")) });
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// The basic blocks in Graphviz DOT, jumps as labelled edges, the loop in red
+    /// and unreachable blocks greyed out.
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![
+            "digraph program {".to_string(),
+            "    node [shape=box, fontname=monospace];".to_string(),
+            "    start [shape=point];".to_string(),
+            "    end [shape=doublecircle];".to_string()
+        ];
+
+        for block in &self.blocks {
+            let label = (block.start..block.end)
+                .map(|pc| format!("{}: {}\\l", pc, self.program[pc]))
+                .collect::<String>();
+            let style =
+                if block.in_loop {
+                    ", color=red"
+                } else if !block.reachable {
+                    ", style=dashed, color=gray, fontcolor=gray"
+                } else {
+                    ""
+                };
+            lines.push(format!("    b{} [label=\"{}\"{}];", block.start, label, style));
         }
 
-        let mut console = Console::from_program(&program);
-        console.run_until_loop_or_end();
-        if console.pc >= input.len() {
-            return console.accumulator;
+        // Node IDs can't have a '-', so out of bounds pcs before the start are written as out_m<pc>
+        let out_id = |pc: isize| if pc < 0 { format!("out_m{}", -pc) } else { format!("out{}", pc) };
+        let out_of_bounds = self.blocks.iter()
+            .filter_map(|block| match block.next {
+                Target::OutOfBounds(pc) => Some(pc),
+                _ => None
+            })
+            .collect::<BTreeSet<_>>();
+        for &pc in &out_of_bounds {
+            lines.push(format!("    {} [shape=octagon, label=\"out of bounds\\npc {}\"];", out_id(pc), pc));
         }
 
-        program[i] = old_instruction;
+        if !self.blocks.is_empty() {
+            lines.push("    start -> b0;".to_string());
+        } else {
+            lines.push("    start -> end;".to_string());
+        }
+        for block in &self.blocks {
+            let last = self.program[block.end - 1];
+            let to = match block.next {
+                Target::Pc(pc) => format!("b{}", pc),
+                Target::End => "end".to_string(),
+                Target::OutOfBounds(pc) => out_id(pc)
+            };
+            let mut attributes = vec![];
+            if last.opcode == OpCode::Jmp {
+                attributes.push(format!("label=\"{}\"", last));
+            }
+            if block.in_loop && self.blocks.iter().any(|b| b.in_loop && Target::Pc(b.start) == block.next) {
+                attributes.push("color=red".to_string());
+            }
+            let attributes = if attributes.is_empty() { String::new() } else { format!(" [{}]", attributes.join(", ")) };
+            lines.push(format!("    b{} -> {}{};", block.start, to, attributes));
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
+
+fn is_label(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Assembles a console program written with labels, `#` comments and labels as `jmp` and `nop`
+/// arguments, into instructions with relative offsets. A label is defined by `name:` before an
+/// instruction or on a line of its own, a label after the last instruction marks the end.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, ParseError> {
+    let mut labels = HashMap::new();
+    let mut code = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let mut rest = line.split('#').next().unwrap_or_default().trim();
+        while let Some(idx) = rest.find(':') {
+            let label = rest[..idx].trim();
+            if !is_label(label) {
+                return Err(ParseError::at(line, label, "label of letters, digits and '_'").with_line(i + 1));
+            }
+            if labels.insert(label, code.len()).is_some() {
+                return Err(ParseError::at(line, label, "label not defined before").with_line(i + 1));
+            }
+            rest = rest[(idx + 1)..].trim();
+        }
+        if !rest.is_empty() {
+            code.push((i + 1, line, rest));
+        }
     }
 
-    0
+    let lower = |pc: usize, line: &str, rest: &str| -> Result<Instruction, ParseError> {
+        let mut parts = rest.split_ascii_whitespace();
+        let opcode = parse_token(line, parts.next(), "opcode 'nop', 'acc' or 'jmp'")?;
+        let expected = if opcode == OpCode::Acc { "signed integer argument" } else { "signed integer argument or label" };
+        let token = expect_token(line, parts.next(), expected)?;
+        let arg = match labels.get(token) {
+            Some(&target) if opcode != OpCode::Acc => target as i32 - pc as i32,
+            _ => parse_token(line, Some(token), expected)?
+        };
+        if let Some(extra) = parts.next() {
+            return Err(ParseError::at(line, extra, "end of line"));
+        }
+        Ok(Instruction { opcode, arg })
+    };
+
+    code.iter()
+        .enumerate()
+        .map(|(pc, &(number, line, rest))| lower(pc, line, rest).map_err(|e| e.with_line(number)))
+        .collect()
 }
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        println!("Part1: {}", part1(&input));
-        println!("Part2: {}", part2(&input));
-    });
+/// The program as `assemble` reads it, with a label `l<pc>` for every target of a `jmp`
+/// and `end` if one jumps right past the last instruction.
+pub fn to_assembly(program: &[Instruction]) -> String {
+    let target = |pc: usize, instruction: &Instruction| match Target::of(program, pc as isize + instruction.offset()) {
+        Target::Pc(target) if instruction.opcode == OpCode::Jmp => Some(format!("l{}", target)),
+        Target::End if instruction.opcode == OpCode::Jmp => Some("end".to_string()),
+        _ => None
+    };
+    let targets = program.iter()
+        .enumerate()
+        .filter_map(|(pc, instruction)| target(pc, instruction))
+        .collect::<HashSet<_>>();
+
+    let mut lines = vec![];
+    for (pc, instruction) in program.iter().enumerate() {
+        let label = format!("l{}", pc);
+        if targets.contains(&label) {
+            lines.push(format!("{}:", label));
+        }
+        lines.push(match target(pc, instruction) {
+            Some(target) => format!("    jmp {}", target),
+            None => format!("    {}", instruction)
+        });
+    }
+    if targets.contains("end") {
+        lines.push("end:".to_string());
+    }
+    lines.join("\n")
 }
 
 impl FromStr for OpCode {
@@ -123,27 +469,33 @@ impl FromStr for OpCode {
         }
     }
 }
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let opcode = match self.opcode {
+            OpCode::Nop => "nop",
+            OpCode::Acc => "acc",
+            OpCode::Jmp => "jmp"
+        };
+        write!(f, "{} {:+}", opcode, self.arg)
+    }
+}
+
 impl FromStr for Instruction {
-    type Err = ParseIntError;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parts = s.split_ascii_whitespace();
-        let opcode = parts.next().and_then(|s| s.parse::<OpCode>().ok()).unwrap();
-        let arg = parts.next().and_then(|s| s.parse::<i32>().ok()).unwrap();
+        let opcode = parse_token(s, parts.next(), "opcode 'nop', 'acc' or 'jmp'")?;
+        let arg = parse_token(s, parts.next(), "signed integer argument")?;
 
-        Ok(Instruction { opcode, arg })
+        match parts.next() {
+            Some(extra) => Err(ParseError::at(s, extra, "end of line")),
+            None => Ok(Instruction { opcode, arg })
+        }
     }
 }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap().parse::<Instruction>().unwrap()).collect())
-}
-
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
-}
-
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -159,16 +511,114 @@ mod tests {
         acc +6";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day08::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&as_input(INPUT)), 5);
+        assert_eq!(Day08::part1(&as_input(INPUT)), 5);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&as_input(INPUT)), 8);
+        assert_eq!(Day08::part2(&as_input(INPUT)), Repair::Flip { pc: 7, acc: 8 });
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let program = as_input(INPUT);
+        let control_flow = ControlFlow::new(&program);
+        assert_eq!(control_flow.blocks.iter().map(|b| (b.start, b.next, b.reachable, b.in_loop)).collect::<Vec<_>>(), vec![
+            (0, Target::Pc(1), true, false),
+            (1, Target::Pc(6), true, true),
+            (3, Target::Pc(1), true, true),
+            (5, Target::Pc(6), false, false),
+            (6, Target::Pc(3), true, true),
+            (8, Target::End, false, false)
+        ]);
+        assert_eq!(control_flow.jumps_to[1], vec![4]);
+        assert!(control_flow.disassemble().contains("*     1  acc +1      ; loop entry, <- 4"));
+        assert!(control_flow.to_dot().contains("b1 -> b6 [label=\"jmp +4\", color=red];"));
+
+        let program = as_input("jmp +2\njmp -5\nacc +1");
+        let control_flow = ControlFlow::new(&program);
+        assert_eq!(control_flow.blocks[1].next, Target::OutOfBounds(-4));
+        assert!(control_flow.to_dot().contains("b2 -> end;"));
+
+        let program = as_input("jmp +3\njmp -5\nacc +1\njmp -7\njmp +9");
+        let dot = ControlFlow::new(&program).to_dot();
+        assert_eq!(dot.matches("out_m4 [shape=octagon, label=\"out of bounds\\npc -4\"];").count(), 1);
+        assert!(dot.contains("b1 -> out_m4 [label=\"jmp -5\"];"));
+        assert!(dot.contains("b3 -> out_m4 [label=\"jmp -7\"];"));
+        assert!(dot.contains("b4 -> out13 [label=\"jmp +9\"];"));
+    }
+
+    #[test]
+    fn test_assemble() {
+        let source =
+           "# The example program
+                nop +0
+            loop:
+                acc +1
+                jmp inner
+            outer:  acc +3
+                jmp loop
+                acc -99
+            inner: acc +1  # jumped to from loop
+                jmp outer
+                acc +6";
+        let program = assemble(source).unwrap();
+        assert_eq!(program, as_input(INPUT));
+        assert_eq!(to_assembly(&program), "    nop +0\nl1:\n    acc +1\n    jmp l6\nl3:\n    acc +3\n    jmp l1\n    acc -99\nl6:\n    acc +1\n    jmp l3\n    acc +6");
+        assert_eq!(assemble(&to_assembly(&program)).unwrap(), program);
+
+        let program = assemble("start: jmp end\njmp start\nend:").unwrap();
+        assert_eq!(program.iter().map(|i| i.to_string()).collect::<Vec<_>>(), vec!["jmp +2", "jmp -1"]);
+        assert_eq!(to_assembly(&program), "l0:\n    jmp end\n    jmp l0\nend:");
+
+        assert_eq!(assemble("nop +0\n  jmp nowhere").unwrap_err().to_string(),
+            "line 2, column 7: expected signed integer argument or label, found \"nowhere\"");
+        assert_eq!(assemble("a: nop +0\na: acc +1").unwrap_err().to_string(),
+            "line 2, column 1: expected label not defined before, found \"a\"");
+        assert!(assemble("a: acc a").is_err());
+        assert!(assemble("1a: acc +1").is_err());
+        assert!(assemble("acc +1 +2").is_err());
+    }
+
+    #[test]
+    fn test_repair() {
+        assert_eq!(repair(&as_input("acc +1\njmp +2\njmp -2")), Repair::NotNeeded { acc: 1 });
+        assert_eq!(repair(&as_input("acc +1\njmp +0\njmp -2")), Repair::Impossible);
+
+        let ambiguous = repair(&as_input("nop +3\njmp +0\nacc +1\nacc +2"));
+        assert_eq!(ambiguous, Repair::Ambiguous(vec![Repair::Flip { pc: 0, acc: 2 }, Repair::Flip { pc: 1, acc: 3 }]));
+        assert_eq!(ambiguous.to_string(), "2 possible repairs: pc 0 gives 2, pc 1 gives 3");
+
+        // Far too slow when rerunning the program for every flip
+        let n = 100_000;
+        let mut program = vec![Instruction { opcode: OpCode::Acc, arg: 1 }; n];
+        program[n - 1] = Instruction { opcode: OpCode::Jmp, arg: 1 - n as i32 };
+        program[n / 2] = Instruction { opcode: OpCode::Nop, arg: n as i32 };
+        assert_eq!(repair(&program), Repair::Flip { pc: n - 1, acc: n as i64 - 2 });
+
+        // As is rerunning the long tail every flip leads into, the jmp included
+        let mut program = (0..n).map(|pc| Instruction { opcode: OpCode::Nop, arg: (n + 1 - pc) as i32 }).collect::<Vec<_>>();
+        program.push(Instruction { opcode: OpCode::Jmp, arg: -(n as i32) });
+        program.extend(vec![Instruction { opcode: OpCode::Acc, arg: 1 }; n]);
+        match repair(&program) {
+            Repair::Ambiguous(repairs) => {
+                assert_eq!(repairs.len(), n + 1);
+                assert!(repairs.iter().enumerate().all(|(pc, r)| *r == Repair::Flip { pc, acc: n as i64 }));
+            },
+            repair => panic!("Expected ambiguous repairs, got {:?}", repair)
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = |s: &str| Day08::parse(s).unwrap_err().to_string();
+        assert_eq!(error("nop +0\nacc 1x"), "line 2, column 5: expected signed integer argument, found \"1x\"");
+        assert_eq!(error("nop +0 junk"), "line 1, column 8: expected end of line, found \"junk\"");
+        assert_eq!(error("jump +4"), "line 1, column 1: expected opcode 'nop', 'acc' or 'jmp', found \"jump\"");
     }
 }
@@ -1,12 +1,7 @@
-extern crate utils;
-
-use std::env;
 use std::collections::HashSet;
 use std::collections::VecDeque;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::parse::{ParseError, parse_lines, parse_token};
 
 type Input = Vec<u64>;
 
@@ -15,87 +10,78 @@ const PREAMBLE_SIZE: usize = 5;
 #[cfg(not(test))]
 const PREAMBLE_SIZE: usize = 25;
 
-fn part1(input: &Input) -> u64 {
-    let mut preamble = VecDeque::new();
-
-    for i in 0..PREAMBLE_SIZE {
-        let mut sums = HashSet::with_capacity(PREAMBLE_SIZE);
-        for j in 0..PREAMBLE_SIZE {
-            if i == j {
-                continue;
-            }
-            sums.insert(input[i] + input[j]);
-        }
-        preamble.push_back(sums);
-    }
+pub struct Day09;
 
-    for i in PREAMBLE_SIZE..input.len() {
-        let v = input[i];
+impl Solution for Day09 {
+    const DAY: u32 = 9;
+    type Input = Input;
+    type Part1 = u64;
+    type Part2 = u64;
 
-        if !preamble.iter().any(|sums| sums.contains(&v)) {
-            return v;
-        }
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, |line| parse_token(line, Some(line), "unsigned integer"))
+    }
 
-        preamble.pop_front();
+    fn part1(input: &Input) -> u64 {
+        let mut preamble = VecDeque::new();
 
-        let mut sums = HashSet::with_capacity(PREAMBLE_SIZE);
-        for j in (i - PREAMBLE_SIZE)..i {
-            sums.insert(v + input[j]);
+        for i in 0..PREAMBLE_SIZE {
+            let mut sums = HashSet::with_capacity(PREAMBLE_SIZE);
+            for j in 0..PREAMBLE_SIZE {
+                if i == j {
+                    continue;
+                }
+                sums.insert(input[i] + input[j]);
+            }
+            preamble.push_back(sums);
         }
-        preamble.push_back(sums);
-    }
-
-    0
-}
 
-fn part2(input: &Input, p1: u64) -> u64 {
+        for i in PREAMBLE_SIZE..input.len() {
+            let v = input[i];
 
-    for i in 0..input.len() {
-        let mut sum = 0;
-        let mut min = std::u64::MAX;
-        let mut max = std::u64::MIN;
+            if !preamble.iter().any(|sums| sums.contains(&v)) {
+                return v;
+            }
 
-        for j in i..input.len() {
-            let v = input[j];
-            sum += v;
-            min = std::cmp::min(v, min);
-            max = std::cmp::max(v, max);
+            preamble.pop_front();
 
-            if sum == p1 {
-                return min + max;
-            } else if sum > p1 {
-                break;
+            let mut sums = HashSet::with_capacity(PREAMBLE_SIZE);
+            for w in &input[(i - PREAMBLE_SIZE)..i] {
+                sums.insert(v + w);
             }
+            preamble.push_back(sums);
         }
+
+        0
     }
 
-    0
-}
+    fn part2(input: &Input) -> u64 {
+        let p1 = Self::part1(input);
 
-fn solve(input: &Input) -> (u64, u64) {
-    let p1 = part1(input);
-    (p1, part2(input, p1))
-}
+        for i in 0..input.len() {
+            let mut sum = 0;
+            let mut min = u64::MAX;
+            let mut max = u64::MIN;
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        let (part1, part2) = solve(&input);
-        println!("Part1: {}", part1);
-        println!("Part2: {}", part2);
-    });
-}
+            for &v in &input[i..] {
+                sum += v;
+                min = std::cmp::min(v, min);
+                max = std::cmp::max(v, max);
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap().parse::<u64>().unwrap()).collect())
-}
+                if sum == p1 {
+                    return min + max;
+                } else if sum > p1 {
+                    break;
+                }
+            }
+        }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+        0
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -122,16 +108,16 @@ mod tests {
         576";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day09::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(solve(&as_input(INPUT)).0, 127);
+        assert_eq!(Day09::part1(&as_input(INPUT)), 127);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(solve(&as_input(INPUT)).1, 62);
+        assert_eq!(Day09::part2(&as_input(INPUT)), 62);
     }
 }
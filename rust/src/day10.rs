@@ -1,11 +1,6 @@
-extern crate utils;
-
-use std::env;
 use std::collections::VecDeque;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::parse::{ParseError, parse_lines, parse_token};
 
 type Input = Vec<u32>;
 
@@ -29,7 +24,7 @@ fn n_combinations(n_diffs: usize) -> usize {
     cnt
 }
 
-fn solve(input: &Input) -> (usize, usize) {
+fn joltage_diffs(input: &Input) -> Vec<u32> {
     let mut input = input.clone();
     input.push(0);
     input.sort();
@@ -44,37 +39,39 @@ fn solve(input: &Input) -> (usize, usize) {
         last = i;
     }
 
-    let p1 = diffs.iter().filter(|&&n| n == 1).count()
-           * diffs.iter().filter(|&&n| n == 3).count();
+    diffs
+}
 
-    let p2 = diffs
-        .split(|&diff| diff == 3)
-        .fold(1, |cnt, ones| {
-            cnt * n_combinations(ones.len())
-        });
+pub struct Day10;
 
-    (p1, p2)
-}
+impl Solution for Day10 {
+    const DAY: u32 = 10;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        let (part1, part2) = solve(&input);
-        println!("Part1: {}", part1);
-        println!("Part2: {}", part2);
-    });
-}
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, |line| parse_token(line, Some(line), "unsigned integer"))
+    }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap().parse::<u32>().unwrap()).collect())
-}
+    fn part1(input: &Input) -> usize {
+        let diffs = joltage_diffs(input);
+
+        diffs.iter().filter(|&&n| n == 1).count()
+            * diffs.iter().filter(|&&n| n == 3).count()
+    }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+    fn part2(input: &Input) -> usize {
+        joltage_diffs(input)
+            .split(|&diff| diff == 3)
+            .fold(1, |cnt, ones| {
+                cnt * n_combinations(ones.len())
+            })
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -125,18 +122,18 @@ mod tests {
         3";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day10::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(solve(&as_input(INPUT1)).0, 7 * 5);
-        assert_eq!(solve(&as_input(INPUT2)).0, 22 * 10);
+        assert_eq!(Day10::part1(&as_input(INPUT1)), 7 * 5);
+        assert_eq!(Day10::part1(&as_input(INPUT2)), 22 * 10);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(solve(&as_input(INPUT1)).1, 8);
-        assert_eq!(solve(&as_input(INPUT2)).1, 19208);
+        assert_eq!(Day10::part2(&as_input(INPUT1)), 8);
+        assert_eq!(Day10::part2(&as_input(INPUT2)), 19208);
     }
 }
@@ -1,184 +1,121 @@
-extern crate utils;
+use std::fmt;
+use crate::Solution;
+use crate::grid::{Grid, DIRS8};
+use crate::parse::ParseError;
 
-use std::env;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
-
-type Input = Grid;
+type Input = Grid<GridState>;
 
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
-enum GridState {
+pub enum GridState {
     Floor, EmptySeat, OccupiedSeat
 }
 
 impl GridState {
-    fn parse(chr: char) -> GridState {
+    fn parse(chr: char) -> Option<GridState> {
         match chr {
-            '.' => GridState::Floor,
-            'L' => GridState::EmptySeat,
-            '#' => GridState::OccupiedSeat,
-            _ => unreachable!()
+            '.' => Some(GridState::Floor),
+            'L' => Some(GridState::EmptySeat),
+            '#' => Some(GridState::OccupiedSeat),
+            _ => None
         }
     }
 }
 
-#[derive(Clone, Debug)]
-struct Grid {
-    rows: Vec<Vec<GridState>>
+impl fmt::Display for GridState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chr = match self {
+            GridState::Floor => '.',
+            GridState::EmptySeat => 'L',
+            GridState::OccupiedSeat => '#'
+        };
+        write!(f, "{}", chr)
+    }
 }
 
-impl Grid {
-    fn at(&self, x: usize, y: usize) -> Option<GridState> {
-        self.rows.get(y).and_then(|row| row.get(x).cloned())
-    }
+fn count_occupied_adjacent(grid: &Grid<GridState>, x: isize, y: isize) -> usize {
+    grid.neighbours8(x, y)
+        .filter(|&(_, &s)| s == GridState::OccupiedSeat)
+        .count()
+}
 
-    fn count_occupied_adjacent(&self, x: usize, y: usize) -> usize {
-        let x = x as i32;
-        let y = y as i32;
-        let mut cnt = 0;
-
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let tx = x + dx;
-                let ty = y + dy;
-
-                if tx >= 0 && ty >= 0 {
-                    cnt += self.at(tx as usize, ty as usize)
-                        .filter(|&s| s == GridState::OccupiedSeat)
-                        .map(|_| 1)
-                        .unwrap_or(0);
-
-                }
-            }
-        }
-        cnt
-    }
+fn count_occupied_directional(grid: &Grid<GridState>, x: isize, y: isize) -> usize {
+    DIRS8.iter()
+        .filter(|&&(dx, dy)| {
+            let seat = grid.ray(x, y, dx, dy).map(|(_, &s)| s).find(|&s| s != GridState::Floor);
+            seat == Some(GridState::OccupiedSeat)
+        })
+        .count()
+}
+
+fn num_occupied(grid: &Grid<GridState>) -> usize {
+    grid.cells().iter().filter(|&&s| s == GridState::OccupiedSeat).count()
+}
+
+fn step(grid: &Grid<GridState>,
+        occupied_threshold: usize,
+        count_occupied: fn(&Grid<GridState>, isize, isize) -> usize) -> (usize, Grid<GridState>) {
+    let mut next_grid = grid.clone();
+    let mut num_changed = 0;
 
-    fn count_occupied_directional(&self, x: usize, y: usize) -> usize {
-        let x = x as i32;
-        let y = y as i32;
-        let mut cnt = 0;
-
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-
-                let mut tx = x;
-                let mut ty = y;
-
-                let seat = loop {
-                    tx += dx;
-                    ty += dy;
-
-                    if tx >= 0 && ty >= 0 {
-                        match self.at(tx as usize, ty as usize) {
-                            Some(GridState::Floor) => continue,
-                            at => break at
-                        }
-
-                    } else {
-                        break None;
-                    }
-                };
-
-                cnt += seat
-                    .filter(|&s| s == GridState::OccupiedSeat)
-                    .map(|_| 1)
-                    .unwrap_or(0);
-            }
+    for ((x, y), &state) in grid.iter() {
+        if state == GridState::Floor {
+            continue;
         }
-        cnt
-    }
 
-    fn num_occupied(&self) -> usize{
-        self.rows.iter()
-            .map(|row| row.iter().filter(|&&s| s == GridState::OccupiedSeat).count())
-            .sum()
-    }
+        let adj_count = count_occupied(grid, x, y);
+
+        let next_state =
+            if state == GridState::EmptySeat && adj_count == 0 {
+                GridState::OccupiedSeat
+            } else if state == GridState::OccupiedSeat && adj_count >= occupied_threshold {
+                GridState::EmptySeat
+            } else {
+                state
+            };
 
-    fn step(&self, occupied_threshold: usize, count_occupied: fn(&Grid, usize, usize) -> usize) -> (usize, Grid) {
-        let mut next_grid = self.clone();
-        let mut num_changed = 0;
-
-        for y in 0..self.rows.len() {
-            let row = &self.rows[y];
-
-            for x in 0..row.len() {
-                if let Some(state) = self.at(x, y) {
-                    if state == GridState::Floor {
-                        continue;
-                    }
-
-                    let adj_count = count_occupied(&self, x, y);
-
-                    let next_state =
-                        if state == GridState::EmptySeat && adj_count == 0 {
-                            GridState::OccupiedSeat
-                        } else if state == GridState::OccupiedSeat && adj_count >= occupied_threshold {
-                            GridState::EmptySeat
-                        } else {
-                            state
-                        };
-
-                    if next_state != next_grid.rows[y][x] {
-                        next_grid.rows[y][x] = next_state;
-                        num_changed += 1;
-                    }
-                }
-            }
+        if next_state != state {
+            next_grid[(x, y)] = next_state;
+            num_changed += 1;
         }
-        (num_changed, next_grid)
     }
+    (num_changed, next_grid)
 }
 
-fn num_occupied_when_stable(mut grid: Grid,
+fn num_occupied_when_stable(mut grid: Grid<GridState>,
                             occupied_threshold: usize,
-                            count_occupied: fn(&Grid, usize, usize) -> usize) -> usize {
+                            count_occupied: fn(&Grid<GridState>, isize, isize) -> usize) -> usize {
     loop {
-        let (num_changed, next_grid) = grid.step(occupied_threshold, count_occupied);
+        let (num_changed, next_grid) = step(&grid, occupied_threshold, count_occupied);
         if num_changed == 0 {
-            break next_grid.num_occupied()
+            break num_occupied(&next_grid)
         }
         grid = next_grid;
     }
 }
 
-fn part1(input: &Input) -> usize {
-    num_occupied_when_stable(input.clone(), 4, Grid::count_occupied_adjacent)
-}
+pub struct Day11;
 
-fn part2(input: &Input) -> usize {
-    num_occupied_when_stable(input.clone(), 5, Grid::count_occupied_directional)
-}
+impl Solution for Day11 {
+    const DAY: u32 = 11;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        println!("Part1: {}", part1(&input));
-        println!("Part2: {}", part2(&input));
-    });
-}
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        Grid::parse(input, "'.', 'L' or '#'", GridState::parse)
+    }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    let rows = reader.lines()
-        .map(|l| l.unwrap().chars().map(GridState::parse).collect::<Vec<_>>())
-        .collect();
-    Ok(Grid { rows })
-}
+    fn part1(input: &Input) -> usize {
+        num_occupied_when_stable(input.clone(), 4, count_occupied_adjacent)
+    }
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+    fn part2(input: &Input) -> usize {
+        num_occupied_when_stable(input.clone(), 5, count_occupied_directional)
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -195,16 +132,16 @@ mod tests {
         L.LLLLL.LL";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day11::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&as_input(INPUT)), 37);
+        assert_eq!(Day11::part1(&as_input(INPUT)), 37);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&as_input(INPUT)), 26);
+        assert_eq!(Day11::part2(&as_input(INPUT)), 26);
     }
 }
@@ -1,12 +1,7 @@
-extern crate utils;
-
-use std::env;
 use std::str::FromStr;
-use std::num::ParseIntError;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::geom::{Vec2, Dir};
+use crate::parse::{ParseError, parse_lines, parse_token};
 
 type Input = Vec<Instruction>;
 
@@ -16,51 +11,11 @@ enum Action {
 }
 
 #[derive(Debug)]
-struct Instruction {
+pub struct Instruction {
     action: Action,
     value: i32
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
-struct Vec2 {
-    x: i32,
-    y: i32
-}
-
-impl Vec2 {
-    fn new(x: i32, y: i32) -> Self {
-        Vec2 { x, y }
-    }
-
-    fn translate(&self, by: Vec2) -> Self {
-        Vec2::new(self.x + by.x, self.y + by.y)
-    }
-
-    fn mul(&self, by: i32) -> Self {
-        Vec2::new(self.x * by, self.y * by)
-    }
-
-    fn rotate(&self, mut by: i32) -> Self {
-        if by < 0 {
-            by += 360;
-        }
-        let mut current = self.clone();
-
-        for _ in 0..(by / 90) {
-            let next_x = current.y * -1;
-            let next_y = current.x;
-            current.x = next_x;
-            current.y = next_y;
-        }
-        current
-    }
-}
-
-const DIR_E: Vec2 = Vec2 { x: 1, y: 0 };
-const DIR_W: Vec2 = Vec2 { x: -1, y: 0 };
-const DIR_N: Vec2 = Vec2 { x: 0, y: -1 };
-const DIR_S: Vec2 = Vec2 { x: 0, y: 1 };
-
 #[derive(Debug)]
 struct Ship {
     pos: Vec2,
@@ -69,96 +24,77 @@ struct Ship {
 
 impl Ship {
     fn new() -> Self {
-        Ship { pos: Vec2::new(0, 0), dir: Vec2 { x: 1, y: 0 } }
+        Ship { pos: Vec2::zero(), dir: Dir::E.vec() }
     }
 
     fn run(&mut self, ins: &Instruction) {
         match ins.action {
-            Action::N => {
-                self.pos = self.pos.translate(Vec2::new(0, ins.value * -1));
-            },
-            Action::S => {
-                self.pos = self.pos.translate(Vec2::new(0, ins.value));
-            },
-            Action::E => {
-                self.pos = self.pos.translate(Vec2::new(ins.value, 0));
-            },
-            Action::W => {
-                self.pos = self.pos.translate(Vec2::new(ins.value * -1, 0));
-            },
-            Action::L => {
-                self.dir = self.dir.rotate(ins.value * -1);
-            },
-            Action::R => {
-                self.dir = self.dir.rotate(ins.value);
-            },
-            Action::F => {
-                self.pos = self.pos.translate(self.dir.mul(ins.value));
-            }
+            Action::N => self.pos += Dir::N.vec() * ins.value,
+            Action::S => self.pos += Dir::S.vec() * ins.value,
+            Action::E => self.pos += Dir::E.vec() * ins.value,
+            Action::W => self.pos += Dir::W.vec() * ins.value,
+            Action::L => self.dir = self.dir.rotate(-ins.value),
+            Action::R => self.dir = self.dir.rotate(ins.value),
+            Action::F => self.pos += self.dir * ins.value
         }
     }
 
     fn run_with_waypoint(&mut self, ins: &Instruction, mut wp: Vec2) -> Vec2 {
         match ins.action {
-            Action::N => {
-                wp = wp.translate(DIR_N.mul(ins.value));
-            },
-            Action::S => {
-                wp = wp.translate(DIR_S.mul(ins.value));
-            },
-            Action::E => {
-                wp = wp.translate(DIR_E.mul(ins.value));
-            },
-            Action::W => {
-                wp = wp.translate(DIR_W.mul(ins.value));
-            },
-            Action::L => {
-                wp = wp.rotate(ins.value * -1);
-            },
-            Action::R => {
-                wp = wp.rotate(ins.value);
-            },
-            Action::F => {
-                self.pos = self.pos.translate(wp.mul(ins.value));
-            }
+            Action::N => wp += Dir::N.vec() * ins.value,
+            Action::S => wp += Dir::S.vec() * ins.value,
+            Action::E => wp += Dir::E.vec() * ins.value,
+            Action::W => wp += Dir::W.vec() * ins.value,
+            Action::L => wp = wp.rotate(-ins.value),
+            Action::R => wp = wp.rotate(ins.value),
+            Action::F => self.pos += wp * ins.value
         }
         wp
     }
 }
 
-fn part1(input: &Input) -> i32 {
-    let mut ship = Ship::new();
+pub struct Day12;
 
-    for ins in input {
-        ship.run(ins);
+impl Solution for Day12 {
+    const DAY: u32 = 12;
+    type Input = Input;
+    type Part1 = i32;
+    type Part2 = i32;
+
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, str::parse::<Instruction>)
     }
 
-    ship.pos.x.abs() + ship.pos.y.abs()
-}
+    fn part1(input: &Input) -> i32 {
+        let mut ship = Ship::new();
 
-fn part2(input: &Input) -> i32 {
-    let mut ship = Ship::new();
-    let mut wp = Vec2::new(10, -1);
+        for ins in input {
+            ship.run(ins);
+        }
 
-    for ins in input {
-        wp = ship.run_with_waypoint(ins, wp);
+        ship.pos.manhattan()
     }
 
-    ship.pos.x.abs() + ship.pos.y.abs()
-}
+    fn part2(input: &Input) -> i32 {
+        let mut ship = Ship::new();
+        let mut wp = Vec2::new(10, -1);
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        println!("Part1: {}", part1(&input));
-        println!("Part2: {}", part2(&input));
-    });
+        for ins in input {
+            wp = ship.run_with_waypoint(ins, wp);
+        }
+
+        ship.pos.manhattan()
+    }
 }
 
 impl FromStr for Instruction {
-    type Err = ParseIntError;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let action = match &s[0..1] {
+        const EXPECTED_ACTION: &str = "action 'N', 'S', 'E', 'W', 'L', 'R' or 'F'";
+        let split = s.chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+        let (action, value) = s.split_at(split);
+
+        let action = match action {
             "N" => Action::N,
             "S" => Action::S,
             "E" => Action::E,
@@ -166,9 +102,13 @@ impl FromStr for Instruction {
             "L" => Action::L,
             "R" => Action::R,
             "F" => Action::F,
-            _ => unreachable!()
+            "" => return Err(ParseError::at_end(s, EXPECTED_ACTION)),
+            _ => return Err(ParseError::at(s, action, EXPECTED_ACTION))
         };
-        let value = s[1..].parse::<i32>()?;
+        let value = parse_token(s, Some(value).filter(|v| !v.is_empty()), "integer value")?;
+        if matches!(action, Action::L | Action::R) && value % 90 != 0 {
+            return Err(ParseError::at(s, &s[split..], "turn in multiples of 90 degrees"));
+        }
 
         Ok(Instruction {
             action,
@@ -177,16 +117,8 @@ impl FromStr for Instruction {
     }
 }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap().parse::<Instruction>().unwrap()).collect())
-}
-
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
-}
-
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -198,48 +130,32 @@ mod tests {
         F11";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day12::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
-    fn test_vec2_rotate() {
-        // 90
-        assert_eq!(DIR_E.rotate(90), DIR_S);
-        assert_eq!(DIR_S.rotate(90), DIR_W);
-        assert_eq!(DIR_W.rotate(90), DIR_N);
-        assert_eq!(DIR_N.rotate(90), DIR_E);
-
-        // -90
-        assert_eq!(DIR_E.rotate(-90), DIR_N);
-        assert_eq!(DIR_N.rotate(-90), DIR_W);
-        assert_eq!(DIR_W.rotate(-90), DIR_S);
-        assert_eq!(DIR_S.rotate(-90), DIR_E);
-
-        // 270
-        assert_eq!(DIR_E.rotate(270), DIR_N);
-        assert_eq!(DIR_S.rotate(270), DIR_E);
-        assert_eq!(DIR_W.rotate(270), DIR_S);
-        assert_eq!(DIR_N.rotate(270), DIR_W);
-
-        // -270
-        assert_eq!(DIR_E.rotate(-270), DIR_S);
-        assert_eq!(DIR_S.rotate(-270), DIR_W);
-        assert_eq!(DIR_W.rotate(-270), DIR_N);
-        assert_eq!(DIR_N.rotate(-270), DIR_E);
-
-        // Waypoint
-        assert_eq!(Vec2::new(10, -4).rotate(90), Vec2::new(4, 10));
-        assert_eq!(Vec2::new(10, -4).rotate(180), Vec2::new(-10, 4));
-        assert_eq!(Vec2::new(10, -4).rotate(270), Vec2::new(-4, -10));
+    fn test_part1() {
+        assert_eq!(Day12::part1(&as_input(INPUT)), 25);
     }
 
     #[test]
-    fn test_part1() {
-        assert_eq!(part1(&as_input(INPUT)), 25);
+    fn test_part2() {
+        assert_eq!(Day12::part2(&as_input(INPUT)), 286);
     }
 
     #[test]
-    fn test_part2() {
-        assert_eq!(part2(&as_input(INPUT)), 286);
+    fn test_parse_error() {
+        let error = |s: &str| Day12::parse(s).unwrap_err().to_string();
+        assert_eq!(error("F10\nX3"), "line 2, column 1: expected action 'N', 'S', 'E', 'W', 'L', 'R' or 'F', found \"X\"");
+        assert_eq!(error("N"), "line 1, column 2: expected integer value, found end of line");
+        assert_eq!(error("F1.5"), "line 1, column 2: expected integer value, found \"1.5\"");
+    }
+
+    #[test]
+    fn test_parse_turns() {
+        let error = |s: &str| Day12::parse(s).unwrap_err().to_string();
+        assert_eq!(error("F10\nR45"), "line 2, column 2: expected turn in multiples of 90 degrees, found \"45\"");
+        assert_eq!(error("L-180\nL270\nR-30"), "line 3, column 2: expected turn in multiples of 90 degrees, found \"-30\"");
+        assert!(Day12::parse("L-180\nR270\nF45").is_ok());
     }
 }
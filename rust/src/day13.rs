@@ -1,87 +1,89 @@
-extern crate utils;
-
-use std::env;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::parse::{ParseError, parse_token};
 
 #[derive(Debug)]
-struct Input {
+pub struct Input {
     earliest_ts: u64,
     bus_ids: Vec<Option<u64>>
 }
 
-fn part1(input: &Input) -> u64 {
-    let (least_wait, bus_id) = input.bus_ids.iter()
-        .flatten()
-        .fold((std::u64::MAX, 0), |(least_wait_time, least_wait_bus_id), &bus_id| {
-            let time_left = bus_id - (input.earliest_ts % bus_id);
+pub struct Day13;
 
-            if time_left < least_wait_time {
-                (time_left, bus_id)
-            } else {
-                (least_wait_time, least_wait_bus_id)
-            }
-        });
+impl Solution for Day13 {
+    const DAY: u32 = 13;
+    type Input = Input;
+    type Part1 = u64;
+    type Part2 = u64;
 
-    least_wait * bus_id
-}
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        let mut lines = input.lines();
+
+        let line = lines.next().unwrap_or("");
+        let earliest_ts = parse_token(line, Some(line), "earliest timestamp")
+            .map_err(|e| e.with_line(1))?;
+
+        let line = lines.next().unwrap_or("");
+        let bus_ids = line.split(',')
+            .map(|id| match id {
+                "x" => Ok(None),
+                _ => parse_token(line, Some(id), "bus id or 'x'").map(Some)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.with_line(2))?;
+
+        Ok(Input { earliest_ts, bus_ids })
+    }
+
+    fn part1(input: &Input) -> u64 {
+        let (least_wait, bus_id) = input.bus_ids.iter()
+            .flatten()
+            .fold((u64::MAX, 0), |(least_wait_time, least_wait_bus_id), &bus_id| {
+                let time_left = bus_id - (input.earliest_ts % bus_id);
+
+                if time_left < least_wait_time {
+                    (time_left, bus_id)
+                } else {
+                    (least_wait_time, least_wait_bus_id)
+                }
+            });
+
+        least_wait * bus_id
+    }
 
-fn part2(input: &Input) -> u64 {
+    fn part2(input: &Input) -> u64 {
 
-    let mut start = 0;
-    let mut step = input.bus_ids[0].unwrap();
+        let mut start = 0;
+        let mut step = input.bus_ids[0].unwrap();
 
-    for i in 1..input.bus_ids.len() {
-        if let Some(bus_id) = input.bus_ids[i] {
-            let bus_id = bus_id;
+        for i in 1..input.bus_ids.len() {
+            if let Some(bus_id) = input.bus_ids[i] {
 
-            let mut found = None;
+                let mut found = None;
 
-            for t in (start..).step_by(step as usize) {
+                for t in (start..).step_by(step as usize) {
 
-                if (t + i as u64) % bus_id == 0 {
-                    if let Some(found) = found {
-                        step = t - found;
-                        start = found + step;
-                        break;
-                    } else {
-                        if i == input.bus_ids.len() - 1 {
-                            return t;
+                    if (t + i as u64).is_multiple_of(bus_id) {
+                        if let Some(found) = found {
+                            step = t - found;
+                            start = found + step;
+                            break;
+                        } else {
+                            if i == input.bus_ids.len() - 1 {
+                                return t;
+                            }
+                            found = Some(t)
                         }
-                        found = Some(t)
                     }
                 }
             }
         }
-    }
-
-    0
-}
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        println!("Part1: {}", part1(&input));
-        println!("Part2: {}", part2(&input));
-    });
-}
-
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    let mut lines = reader.lines();
-    Ok(Input {
-        earliest_ts: lines.next().unwrap()?.parse::<u64>().unwrap(),
-        bus_ids: lines.next().unwrap()?.split(',').map(|i| i.parse::<u64>().ok()).collect::<Vec<_>>()
-    })
-}
-
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+        0
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -90,21 +92,28 @@ mod tests {
         7,13,x,x,59,x,31,19";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day13::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&as_input(INPUT)), 295);
+        assert_eq!(Day13::part1(&as_input(INPUT)), 295);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&as_input(INPUT)), 1068781);
-        assert_eq!(part2(&as_input("0\n17,x,13,19")), 3417);
-        assert_eq!(part2(&as_input("0\n67,7,59,61")), 754018);
-        assert_eq!(part2(&as_input("0\n67,x,7,59,61")), 779210);
-        assert_eq!(part2(&as_input("0\n67,7,x,59,61")), 1261476);
-        assert_eq!(part2(&as_input("0\n1789,37,47,1889")), 1202161486);
+        assert_eq!(Day13::part2(&as_input(INPUT)), 1068781);
+        assert_eq!(Day13::part2(&as_input("0\n17,x,13,19")), 3417);
+        assert_eq!(Day13::part2(&as_input("0\n67,7,59,61")), 754018);
+        assert_eq!(Day13::part2(&as_input("0\n67,x,7,59,61")), 779210);
+        assert_eq!(Day13::part2(&as_input("0\n67,7,x,59,61")), 1261476);
+        assert_eq!(Day13::part2(&as_input("0\n1789,37,47,1889")), 1202161486);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = |s: &str| Day13::parse(s).unwrap_err().to_string();
+        assert_eq!(error("939\n7,13,x,y"), "line 2, column 8: expected bus id or 'x', found \"y\"");
+        assert_eq!(error("soon\n7,13"), "line 1, column 1: expected earliest timestamp, found \"soon\"");
     }
 }
@@ -1,14 +1,8 @@
-extern crate utils;
-
-use std::env;
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::fmt;
-use std::num::ParseIntError;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::parse::{ParseError, parse_lines, parse_token, expect_token};
 
 type Input = Vec<Instruction>;
 
@@ -17,7 +11,7 @@ enum MaskOp {
     NoOp, Zero, One
 }
 
-struct BitMask {
+pub struct BitMask {
     bits: [MaskOp; 36]
 }
 
@@ -30,8 +24,8 @@ impl BitMask {
         use MaskOp::*;
         let mut bits = [MaskOp::Zero; 36];
 
-        for i in 0..36 {
-            bits[i] = match (value >> (35 - i)) & 1 {
+        for (i, bit) in bits.iter_mut().enumerate() {
+            *bit = match (value >> (35 - i)) & 1 {
                 0 => Zero,
                 1 => One,
                 _ => unreachable!(),
@@ -47,7 +41,7 @@ impl BitMask {
         for (i, &op) in self.bits.iter().enumerate() {
             match op {
                 Zero => {
-                     value &= std::u64::MAX ^ (1 << (35 - i));
+                     value &= u64::MAX ^ (1 << (35 - i));
                 },
                 One  => {
                     value |= 1 << (35 - i)
@@ -94,34 +88,75 @@ impl fmt::Debug for BitMask {
 }
 
 #[derive(Debug)]
-enum Instruction {
+pub enum Instruction {
     SetMask(BitMask), MemAssign(u64, u64)
 }
 
-fn part1(input: &Input) -> u64 {
-    use Instruction::*;
+pub struct Day14;
 
-    let mut mem = HashMap::new();
-    let mut curr_mask = &BitMask::new();
+impl Solution for Day14 {
+    const DAY: u32 = 14;
+    type Input = Input;
+    type Part1 = u64;
+    type Part2 = u64;
 
-    for ins in input {
-        match ins {
-            SetMask(mask) => curr_mask = mask,
-            MemAssign(addr, value) => {
-                let result = curr_mask.decode(*value);
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        parse_lines(input, str::parse::<Instruction>)
+    }
 
-                #[cfg(feature = "print")] {
-                    println!("value:   {:036b}  (decimal {})", value, value);
-                    println!("mask:    {})", curr_mask.bits_to_s());
-                    println!("value:   {:036b}  (decimal {})\n", result, result);
-                }
+    fn part1(input: &Input) -> u64 {
+        use Instruction::*;
+
+        let mut mem = HashMap::new();
+        let mut curr_mask = &BitMask::new();
+
+        for ins in input {
+            match ins {
+                SetMask(mask) => curr_mask = mask,
+                MemAssign(addr, value) => {
+                    let result = curr_mask.decode(*value);
 
-                mem.insert(addr, result);
+                    #[cfg(feature = "print")] {
+                        println!("value:   {:036b}  (decimal {})", value, value);
+                        println!("mask:    {})", curr_mask.bits_to_s());
+                        println!("value:   {:036b}  (decimal {})\n", result, result);
+                    }
+
+                    mem.insert(addr, result);
+                }
             }
         }
+
+        mem.values().sum::<u64>()
     }
 
-    mem.values().sum::<u64>()
+    fn part2(input: &Input) -> u64 {
+        use Instruction::*;
+
+        let mut mem = HashMap::new();
+        let mut curr_mask = &BitMask::new();
+
+        for ins in input {
+            match ins {
+                SetMask(mask) => curr_mask = mask,
+                MemAssign(addr, value) => {
+                    let result = curr_mask.addr_decode(addr);
+
+                    #[cfg(feature = "print")] {
+                        println!("address: {:036b}  (decimal {})", addr, addr);
+                        println!("mask:    {})", curr_mask.bits_to_s());
+                        println!("result:  {}\n", result.bits_to_s());
+                    }
+
+                    for a in mask_to_addresses(&result) {
+                        mem.insert(a, *value);
+                    }
+                }
+            }
+        }
+
+        mem.values().sum::<u64>()
+    }
 }
 
 fn mask_to_addresses(mask: &BitMask) -> Vec<u64> {
@@ -148,7 +183,7 @@ fn mask_to_addresses(mask: &BitMask) -> Vec<u64> {
             current ^= 1 << (35 - bit_idx);
         }
         else {
-            current &= std::u64::MAX ^ (1 << (35 - bit_idx));
+            current &= u64::MAX ^ (1 << (35 - bit_idx));
             floating_idx -= 1;
 
             loop {
@@ -156,7 +191,7 @@ fn mask_to_addresses(mask: &BitMask) -> Vec<u64> {
                 let bit = current >> (35 - bit_idx) & 1;
 
                 if bit == 1 {
-                    current &= std::u64::MAX ^ (1 << (35 - bit_idx));
+                    current &= u64::MAX ^ (1 << (35 - bit_idx));
                     if floating_idx == 0 {
                         break 'outer;
                     }
@@ -174,82 +209,48 @@ fn mask_to_addresses(mask: &BitMask) -> Vec<u64> {
     addrs
 }
 
-fn part2(input: &Input) -> u64 {
-    use Instruction::*;
-
-    let mut mem = HashMap::new();
-    let mut curr_mask = &BitMask::new();
-
-    for ins in input {
-        match ins {
-            SetMask(mask) => curr_mask = mask,
-            MemAssign(addr, value) => {
-                let result = curr_mask.addr_decode(addr);
-
-                #[cfg(feature = "print")] {
-                    println!("address: {:036b}  (decimal {})", addr, addr);
-                    println!("mask:    {})", curr_mask.bits_to_s());
-                    println!("result:  {}\n", result.bits_to_s());
-                }
-
-                for a in mask_to_addresses(&result) {
-                    mem.insert(a, *value);
-                }
-            }
-        }
-    }
-
-    mem.values().sum::<u64>()
-}
-
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        println!("Part1: {}", part1(&input));
-        println!("Part2: {}", part2(&input));
-    });
-}
-
 impl FromStr for Instruction {
-    type Err = ParseIntError;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use Instruction::*;
         use MaskOp::*;
-        let mut parts = s.split('=').map(|s| s.trim());
-        let a = parts.next().unwrap();
-        let b = parts.next().unwrap();
+        let mut parts = s.splitn(2, '=').map(|s| s.trim());
+        let a = parts.next().unwrap_or(s);
+        let b = expect_token(s, parts.next(), "'='")?;
 
         let ins =
-            if s.starts_with("mask") {
+            if a == "mask" {
                 let mut bitmask = BitMask::new();
-                for (i, c) in b.chars().enumerate() {
-                    bitmask.bits[i] = match c {
+                let mut n_bits = 0;
+                for (i, c) in b.char_indices() {
+                    let op = match c {
                         'X' => NoOp,
                         '0' => Zero,
                         '1' => One,
-                        _ => unreachable!()
+                        _ => return Err(ParseError::at(s, &b[i..(i + c.len_utf8())], "'X', '0' or '1'"))
                     };
+                    if n_bits == bitmask.bits.len() {
+                        return Err(ParseError::at(s, &b[i..], "end of 36 bit mask"));
+                    }
+                    bitmask.bits[n_bits] = op;
+                    n_bits += 1;
+                }
+                if n_bits < bitmask.bits.len() {
+                    return Err(ParseError::at_end(s, "'X', '0' or '1'"));
                 }
                 SetMask(bitmask)
+            } else if let Some(addr) = a.strip_prefix("mem[").and_then(|a| a.strip_suffix(']')) {
+                MemAssign(parse_token(s, Some(addr), "memory address")?, parse_token(s, Some(b), "value")?)
             } else {
-                let addr = &a["mem[".len()..(a.len() - 1)];
-                MemAssign(addr.parse::<u64>()?, b.parse::<u64>()?)
+                return Err(ParseError::at(s, a, "'mask' or 'mem[<address>]'"));
             };
 
         Ok(ins)
     }
 }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().map(|l| l.unwrap().parse::<Instruction>().unwrap()).collect())
-}
-
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
-}
-
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -266,16 +267,24 @@ mod tests {
         mem[26] = 1";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day14::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&as_input(INPUT1)), 165);
+        assert_eq!(Day14::part1(&as_input(INPUT1)), 165);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&as_input(INPUT2)), 208);
+        assert_eq!(Day14::part2(&as_input(INPUT2)), 208);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = |s: &str| Day14::parse(s).unwrap_err().to_string();
+        assert_eq!(error("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\nmem[8] 11"), "line 2, column 10: expected '=', found end of line");
+        assert_eq!(error("mask = XX2"), "line 1, column 10: expected 'X', '0' or '1', found \"2\"");
+        assert_eq!(error("mem[a] = 1"), "line 1, column 5: expected memory address, found \"a\"");
     }
 }
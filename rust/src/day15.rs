@@ -1,10 +1,5 @@
-extern crate utils;
-
-use std::env;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::parse::{ParseError, parse_token};
 
 type Input = Vec<u32>;
 
@@ -14,23 +9,21 @@ struct Spoken {
     turns_apart: u32
 }
 
-fn solve(input: &Input) -> (usize, usize) {
-    const NEVER: Spoken = Spoken { last_t: std::u32::MAX, turns_apart: std::u32::MAX };
-    let mut memory: Vec<Spoken> = vec![NEVER; 30000000];
+fn spoken_at(input: &Input, turns: usize) -> usize {
+    const NEVER: Spoken = Spoken { last_t: u32::MAX, turns_apart: u32::MAX };
+    let memory_size = input.iter().map(|&n| n as usize + 1).fold(turns, std::cmp::max);
+    let mut memory: Vec<Spoken> = vec![NEVER; memory_size];
 
     let mut last_spoken = 0;
-    let mut p1_spoken = 0;
 
-    for t in 0..30000000 {
+    for t in 0..turns {
         let spoken =
             if t < input.len() {
                 input[t]
+            } else if memory[last_spoken].turns_apart == 0 || memory[last_spoken] == NEVER {
+                0
             } else {
-                if memory[last_spoken].turns_apart == 0 || memory[last_spoken] == NEVER {
-                    0
-                } else {
-                    memory[last_spoken].turns_apart
-                }
+                memory[last_spoken].turns_apart
             } as usize;
 
         let t = t as u32;
@@ -46,31 +39,35 @@ fn solve(input: &Input) -> (usize, usize) {
             };
         }
 
-        if t == 2020 - 1 {
-            p1_spoken = spoken;
-        }
         last_spoken = spoken;
     }
 
-    (p1_spoken, last_spoken)
+    last_spoken
 }
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        let (part1, part2) = solve(&input);
-        println!("Part1: {}", part1);
-        println!("Part2: {}", part2);
-    });
-}
+pub struct Day15;
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    Ok(reader.lines().next().unwrap()?.split(',').map(|i| i.parse::<u32>().unwrap()).collect())
-}
+impl Solution for Day15 {
+    const DAY: u32 = 15;
+    type Input = Input;
+    type Part1 = usize;
+    type Part2 = usize;
 
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        let line = input.lines().next().unwrap_or("");
+        line.split(',')
+            .map(|n| parse_token(line, Some(n), "starting number"))
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.with_line(1))
+    }
+
+    fn part1(input: &Input) -> usize {
+        spoken_at(input, 2020)
+    }
+
+    fn part2(input: &Input) -> usize {
+        spoken_at(input, 30000000)
+    }
 }
 
 #[cfg(test)]
@@ -78,30 +75,30 @@ mod tests {
     use super::*;
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day15::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(solve(&as_input("0,3,6")).0, 436);
-
-        assert_eq!(solve(&as_input("1,3,2")).0, 1);
-        assert_eq!(solve(&as_input("2,1,3")).0, 10);
-        assert_eq!(solve(&as_input("1,2,3")).0, 27);
-        assert_eq!(solve(&as_input("2,3,1")).0, 78);
-        assert_eq!(solve(&as_input("3,2,1")).0, 438);
-        assert_eq!(solve(&as_input("3,1,2")).0, 1836);
+        assert_eq!(Day15::part1(&as_input("0,3,6")), 436);
+
+        assert_eq!(Day15::part1(&as_input("1,3,2")), 1);
+        assert_eq!(Day15::part1(&as_input("2,1,3")), 10);
+        assert_eq!(Day15::part1(&as_input("1,2,3")), 27);
+        assert_eq!(Day15::part1(&as_input("2,3,1")), 78);
+        assert_eq!(Day15::part1(&as_input("3,2,1")), 438);
+        assert_eq!(Day15::part1(&as_input("3,1,2")), 1836);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(solve(&as_input("0,3,6")).1, 175594);
-
-        assert_eq!(solve(&as_input("1,3,2")).1, 2578);
-        assert_eq!(solve(&as_input("2,1,3")).1, 3544142);
-        assert_eq!(solve(&as_input("1,2,3")).1, 261214);
-        assert_eq!(solve(&as_input("2,3,1")).1, 6895259);
-        assert_eq!(solve(&as_input("3,2,1")).1, 18);
-        assert_eq!(solve(&as_input("3,1,2")).1, 362);
+        assert_eq!(Day15::part2(&as_input("0,3,6")), 175594);
+
+        assert_eq!(Day15::part2(&as_input("1,3,2")), 2578);
+        assert_eq!(Day15::part2(&as_input("2,1,3")), 3544142);
+        assert_eq!(Day15::part2(&as_input("1,2,3")), 261214);
+        assert_eq!(Day15::part2(&as_input("2,3,1")), 6895259);
+        assert_eq!(Day15::part2(&as_input("3,2,1")), 18);
+        assert_eq!(Day15::part2(&as_input("3,1,2")), 362);
     }
 }
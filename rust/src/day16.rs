@@ -1,17 +1,11 @@
-extern crate utils;
-
-use std::env;
 use std::collections::HashSet;
 use std::ops::RangeInclusive;
 use std::str::FromStr;
-use std::num::ParseIntError;
-use std::io::{self, BufReader};
-use std::io::prelude::*;
-use std::fs::File;
-use utils::*;
+use crate::Solution;
+use crate::parse::{ParseError, parse_token, expect_token};
 
 #[derive(Debug)]
-struct Input {
+pub struct Input {
     rules: Vec<TicketRule>,
     tickets: Vec<Ticket>
 }
@@ -33,64 +27,94 @@ struct Ticket {
     fields: Vec<u32>
 }
 
-fn part1(input: &Input) -> u32 {
-    input.tickets.iter()
-        .skip(1)
-        .flat_map(|ticket| {
-            ticket.fields.iter().filter(|&&f| !input.rules.iter().any(|r| r.valid(f)))
-        })
-        .sum()
-}
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: u32 = 16;
+    type Input = Input;
+    type Part1 = u32;
+    type Part2 = u64;
 
-fn part2(input: &Input) -> u64 {
-    let mut valid_tickets: Vec<_> = input.tickets.iter().skip(1)
-        .filter(|ticket| {
-            ticket.fields.iter().filter(|&&f| !input.rules.iter().any(|r| r.valid(f))).count() == 0
-        })
-        .collect();
-
-    let your_ticket = &input.tickets[0];
-    valid_tickets.push(your_ticket);
-
-    let n_fields = valid_tickets[0].fields.len();
-    let n_rules = input.rules.len();
-    let mut possible_rules_by_field: Vec<HashSet<usize>> = vec![HashSet::new(); n_fields];
-
-    for field_idx in 0..n_fields {
-        for rule_idx in 0..n_rules {
-            let rule = &input.rules[rule_idx];
-            if valid_tickets.iter().all(|ticket| rule.valid(ticket.fields[field_idx])) {
-                possible_rules_by_field[field_idx].insert(rule_idx);
+    fn parse(input: &str) -> Result<Input, ParseError> {
+        let mut lines = input.lines().enumerate().map(|(i, l)| (i + 1, l));
+
+        let mut rules = vec![];
+        for (i, line) in lines.by_ref().take_while(|(_, l)| !l.is_empty()) {
+            rules.push(line.parse::<TicketRule>().map_err(|e| e.with_line(i))?);
+        }
+
+        let mut tickets = vec![];
+        for header in &["your ticket:", "nearby tickets:"] {
+            let (i, line) = lines.next().unwrap_or((input.lines().count() + 1, ""));
+            if line != *header {
+                return Err(ParseError::at(line, line, &format!("'{}'", header)).with_line(i));
+            }
+            for (i, line) in lines.by_ref().take_while(|(_, l)| !l.is_empty()) {
+                tickets.push(line.parse::<Ticket>().map_err(|e| e.with_line(i))?);
             }
         }
+
+        Ok(Input { rules, tickets })
     }
 
-    let mut field_ids = vec![None; n_fields];
-    let mut missing_rule_idxs = (0..n_rules).collect::<HashSet<_>>();
+    fn part1(input: &Input) -> u32 {
+        input.tickets.iter()
+            .skip(1)
+            .flat_map(|ticket| {
+                ticket.fields.iter().filter(|&&f| !input.rules.iter().any(|r| r.valid(f)))
+            })
+            .sum()
+    }
 
-    for i in 0.. {
-        let rule_idx = i % n_rules;
+    fn part2(input: &Input) -> u64 {
+        let mut valid_tickets: Vec<_> = input.tickets.iter().skip(1)
+            .filter(|ticket| {
+                ticket.fields.iter().filter(|&&f| !input.rules.iter().any(|r| r.valid(f))).count() == 0
+            })
+            .collect();
 
-        let mut possible_fields = (0..n_fields)
-            .filter(|&field_idx| possible_rules_by_field[field_idx].contains(&rule_idx));
+        let your_ticket = &input.tickets[0];
+        valid_tickets.push(your_ticket);
 
-        if let Some(field_idx) = possible_fields.next() {
+        let n_fields = valid_tickets[0].fields.len();
+        let n_rules = input.rules.len();
+        let mut possible_rules_by_field: Vec<HashSet<usize>> = vec![HashSet::new(); n_fields];
 
-            if possible_fields.next().is_none() {
-                field_ids[field_idx] = Some(&input.rules[rule_idx].id[..]);
-                possible_rules_by_field[field_idx].clear();
-                missing_rule_idxs.remove(&rule_idx);
-                if missing_rule_idxs.is_empty() {
-                    break;
+        for (field_idx, possible_rules) in possible_rules_by_field.iter_mut().enumerate() {
+            for (rule_idx, rule) in input.rules.iter().enumerate() {
+                if valid_tickets.iter().all(|ticket| rule.valid(ticket.fields[field_idx])) {
+                    possible_rules.insert(rule_idx);
                 }
             }
         }
-    }
 
-    field_ids.iter().enumerate()
-        .filter(|(_, field_id)| field_id.matches(|fid| fid.starts_with("departure")))
-        .map(|(i, _)| your_ticket.fields[i] as u64)
-        .product()
+        let mut field_ids = vec![None; n_fields];
+        let mut missing_rule_idxs = (0..n_rules).collect::<HashSet<_>>();
+
+        for i in 0.. {
+            let rule_idx = i % n_rules;
+
+            let mut possible_fields = (0..n_fields)
+                .filter(|&field_idx| possible_rules_by_field[field_idx].contains(&rule_idx));
+
+            if let Some(field_idx) = possible_fields.next() {
+
+                if possible_fields.next().is_none() {
+                    field_ids[field_idx] = Some(&input.rules[rule_idx].id[..]);
+                    possible_rules_by_field[field_idx].clear();
+                    missing_rule_idxs.remove(&rule_idx);
+                    if missing_rule_idxs.is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        field_ids.iter().enumerate()
+            .filter(|(_, field_id)| field_id.matches(|fid| fid.starts_with("departure")))
+            .map(|(i, _)| your_ticket.fields[i] as u64)
+            .product()
+    }
 }
 
 pub trait OptionExt<T> {
@@ -103,59 +127,37 @@ impl<T> OptionExt<T> for Option<T> {
     }
 }
 
-fn main() {
-    measure(|| {
-        let input = input().expect("Input failed");
-        println!("Part1: {}", part1(&input));
-        println!("Part2: {}", part2(&input));
-    });
-}
-
 impl FromStr for TicketRule {
-    type Err = ParseIntError;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split(':').map(|p| p.trim());
-        let id = parts.next().unwrap().to_string();
-        let ranges = parts.next().unwrap()
+        let mut parts = s.splitn(2, ':').map(|p| p.trim());
+        let id = parts.next().unwrap_or(s).to_string();
+        let ranges = expect_token(s, parts.next(), "':'")?
             .split(" or ")
             .map(|r| {
-                let mut sp = r.split('-');
-                sp.next().unwrap().parse::<u32>().unwrap()..=sp.next().unwrap().parse::<u32>().unwrap()
+                let mut sp = r.splitn(2, '-');
+                let from = parse_token(s, sp.next(), "range start")?;
+                let to = parse_token(s, sp.next(), "range end")?;
+                Ok(from..=to)
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         Ok(TicketRule { id, ranges })
     }
 }
 
 impl FromStr for Ticket {
-    type Err = ParseIntError;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let fields = s.split(',').map(|p| p.parse::<u32>().unwrap()).collect();
+        let fields = s.split(',')
+            .map(|p| parse_token(s, Some(p), "ticket field value"))
+            .collect::<Result<_, _>>()?;
         Ok(Ticket { fields })
     }
 }
 
-fn read_input<R: Read>(reader: BufReader<R>) -> io::Result<Input> {
-    let mut lines = reader.lines().flat_map(|l| l.ok());
-
-    let rules = lines.by_ref().take_while(|l| l.len() > 0).map(|l| l.parse::<TicketRule>().unwrap()).collect();
-    let mut tickets = vec![
-        lines.by_ref().skip(1).next().map(|l| l.parse::<Ticket>().unwrap()).unwrap()
-    ];
-    for nearby in lines.by_ref().skip(2).map(|l| l.parse::<Ticket>().unwrap()) {
-        tickets.push(nearby);
-    }
-
-    Ok(Input { rules, tickets })
-}
-
-fn input() -> io::Result<Input> {
-    let f = File::open(env::args().skip(1).next().expect("No input file given"))?;
-    read_input(BufReader::new(f))
-}
-
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes)]
 mod tests {
     use super::*;
 
@@ -174,11 +176,19 @@ mod tests {
         38,6,12";
 
     fn as_input(s: &str) -> Input {
-        read_input(BufReader::new(s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n").as_bytes())).unwrap()
+        Day16::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&as_input(INPUT)), 71);
+        assert_eq!(Day16::part1(&as_input(INPUT)), 71);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = |s: &str| Day16::parse(s).unwrap_err().to_string();
+        assert_eq!(error("class: 1-3 or 5-7\n\nyour ticket:\n7,1,x"), "line 4, column 5: expected ticket field value, found \"x\"");
+        assert_eq!(error("class: 1-3 or 5\n\nyour ticket:\n7"), "line 1, column 16: expected range end, found end of line");
+        assert_eq!(error("class: 1-3\n\nyour tickets:\n7"), "line 3, column 1: expected 'your ticket:', found \"your tickets:\"");
     }
 }
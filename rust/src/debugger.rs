@@ -0,0 +1,311 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+use crate::vm::{Instruction, LoopDetection, Machine, Status};
+
+const HELP: &str =
+"Commands:
+  step [n], s [n]    run n instructions (default 1)
+  continue, c        run until a breakpoint, a watched register changes, an instruction
+                     is about to run a second time or the program ends
+  back [n], bk [n]   undo the last n instructions (default 1, at most the last 100000)
+  break <pc>, b <pc> stop before running the instruction at pc
+  delete <pc>        remove the breakpoint at pc
+  watch <register>   stop when the register changes
+  unwatch <register> stop watching the register
+  print, p           show pc, instruction and registers
+  list, l            show the instructions around pc
+  help, h            show this help
+  quit, q            leave the debugger";
+
+/// How many states the history keeps by default, older ones are dropped.
+pub const HISTORY_LIMIT: usize = 100_000;
+
+/// Interactive debugger for a `Machine`, keeping a history of its latest states to step backwards.
+pub struct Debugger<'a, I> {
+    machine: Machine<'a, I>,
+    register_names: &'a [&'a str],
+    history: VecDeque<Machine<'a, I>>,
+    history_limit: usize,
+    /// Whether states have been dropped from the start of the history.
+    history_truncated: bool,
+    breakpoints: BTreeSet<usize>,
+    watches: BTreeSet<usize>
+}
+
+impl<'a, I: Instruction + Display + Clone> Debugger<'a, I> {
+    /// Debugger for `machine`, whose registers are named by `register_names`.
+    pub fn new(machine: Machine<'a, I>, register_names: &'a [&'a str]) -> Self {
+        Debugger {
+            machine,
+            register_names,
+            history: VecDeque::new(),
+            history_limit: HISTORY_LIMIT,
+            history_truncated: false,
+            breakpoints: BTreeSet::new(),
+            watches: BTreeSet::new()
+        }
+    }
+
+    /// Keeps at most `history_limit` states to step back to instead of `HISTORY_LIMIT`.
+    pub fn with_history_limit(mut self, history_limit: usize) -> Self {
+        self.history_limit = history_limit;
+        while self.history.len() > history_limit {
+            self.history.pop_front();
+            self.history_truncated = true;
+        }
+        self
+    }
+
+    pub fn machine(&self) -> &Machine<'a, I> {
+        &self.machine
+    }
+
+    /// Reads commands from `commands` until it ends or `quit` is given.
+    pub fn run<R: BufRead, W: Write>(&mut self, commands: R, mut out: W) -> io::Result<()> {
+        writeln!(out, "{}", self.describe())?;
+        write!(out, "(debug) ")?;
+        out.flush()?;
+
+        for line in commands.lines() {
+            let line = line?;
+            if matches!(line.trim(), "quit" | "q") {
+                break;
+            }
+            let response = self.command(&line);
+            if !response.is_empty() {
+                writeln!(out, "{}", response)?;
+            }
+            write!(out, "(debug) ")?;
+            out.flush()?;
+        }
+
+        writeln!(out)
+    }
+
+    /// Executes a single command, returning what to show.
+    pub fn command(&mut self, line: &str) -> String {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        let count = |arg: Option<&&str>| arg.map_or(Ok(1), |n| n.parse::<usize>().map_err(|_| format!("Invalid count: {}", n)));
+
+        let result = match parts.as_slice() {
+            [] => Ok(String::new()),
+            ["step", n @ ..] | ["s", n @ ..] if n.len() <= 1 => count(n.first()).map(|n| self.step(n)),
+            ["continue"] | ["c"] => Ok(self.resume()),
+            ["back", n @ ..] | ["bk", n @ ..] if n.len() <= 1 => count(n.first()).map(|n| self.back(n)),
+            ["break", pc] | ["b", pc] => self.pc(pc).map(|pc| {
+                self.breakpoints.insert(pc);
+                format!("Breakpoint at pc {}", pc)
+            }),
+            ["delete", pc] => self.pc(pc).map(|pc| {
+                if self.breakpoints.remove(&pc) {
+                    format!("Removed breakpoint at pc {}", pc)
+                } else {
+                    format!("No breakpoint at pc {}", pc)
+                }
+            }),
+            ["watch", name] => self.register(name).map(|r| {
+                self.watches.insert(r);
+                format!("Watching {} = {}", name, self.machine.registers[r])
+            }),
+            ["unwatch", name] => self.register(name).map(|r| {
+                self.watches.remove(&r);
+                format!("Not watching {}", name)
+            }),
+            ["print"] | ["p"] => Ok(self.describe()),
+            ["list"] | ["l"] => Ok(self.list()),
+            ["help"] | ["h"] => Ok(HELP.to_string()),
+            _ => Err(format!("Unknown command: {} (try help)", line.trim()))
+        };
+
+        result.unwrap_or_else(|e| e)
+    }
+
+    fn pc(&self, pc: &str) -> Result<usize, String> {
+        match pc.parse::<usize>() {
+            Ok(pc) if pc < self.machine.program().len() => Ok(pc),
+            _ => Err(format!("Invalid pc: {} (program has {} instructions)", pc, self.machine.program().len()))
+        }
+    }
+
+    fn register(&self, name: &str) -> Result<usize, String> {
+        self.register_names.iter()
+            .position(|&r| r == name)
+            .ok_or_else(|| format!("Unknown register: {} (registers: {})", name, self.register_names.join(", ")))
+    }
+
+    /// Runs one instruction, returning what changed, or `None` if the machine has stopped.
+    fn single_step(&mut self) -> Option<Vec<String>> {
+        self.machine.current()?;
+        let before = self.machine.clone();
+        self.machine.step();
+
+        let changes = self.watches.iter()
+            .filter(|&&r| before.registers[r] != self.machine.registers[r])
+            .map(|&r| format!("{} changed {} -> {}", self.register_names[r], before.registers[r], self.machine.registers[r]))
+            .collect();
+        if self.history.len() >= self.history_limit {
+            self.history.pop_front();
+            self.history_truncated = true;
+        }
+        if self.history_limit > 0 {
+            self.history.push_back(before);
+        }
+        Some(changes)
+    }
+
+    fn step(&mut self, n: usize) -> String {
+        let mut lines = vec![];
+        for _ in 0..n {
+            match self.single_step() {
+                Some(changes) => lines.extend(changes),
+                None => break
+            }
+        }
+        lines.push(self.describe());
+        lines.join("\n")
+    }
+
+    fn resume(&mut self) -> String {
+        // Detects loops the way the machine does, by pc alone only if jumps don't depend on registers
+        let state = |machine: &Machine<'a, I>| match machine.loop_detection() {
+            LoopDetection::Off => None,
+            LoopDetection::Pc => Some((machine.pc, vec![])),
+            LoopDetection::State => Some((machine.pc, machine.registers.clone()))
+        };
+        let mut visited = self.history.iter().filter_map(state).collect::<HashSet<_>>();
+
+        loop {
+            if let Some(pc) = self.current_pc() {
+                if state(&self.machine).is_some_and(|state| !visited.insert(state)) {
+                    return format!("Loop detected, pc {} is about to run again\n{}", pc, self.describe());
+                }
+            }
+
+            let changes = match self.single_step() {
+                Some(changes) => changes,
+                None => return self.describe()
+            };
+            if !changes.is_empty() {
+                return format!("{}\n{}", changes.join("\n"), self.describe());
+            }
+            if let Some(pc) = self.current_pc().filter(|pc| self.breakpoints.contains(pc)) {
+                return format!("Breakpoint at pc {}\n{}", pc, self.describe());
+            }
+        }
+    }
+
+    fn back(&mut self, n: usize) -> String {
+        for _ in 0..n {
+            match self.history.pop_back() {
+                Some(machine) => self.machine = machine,
+                None if self.history_truncated => return format!(
+                    "At the oldest state kept, the history holds {} states\n{}", self.history_limit, self.describe()
+                ),
+                None => return format!("At the start of the history\n{}", self.describe())
+            }
+        }
+        self.describe()
+    }
+
+    fn current_pc(&self) -> Option<usize> {
+        self.machine.current().map(|_| self.machine.pc as usize)
+    }
+
+    fn registers(&self) -> String {
+        self.register_names.iter()
+            .zip(self.machine.registers.iter())
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The current pc, instruction and registers, or why the machine stopped.
+    pub fn describe(&self) -> String {
+        let state = match (self.machine.current(), self.machine.status()) {
+            (Some(instruction), _) => format!("pc {}: {}", self.machine.pc, instruction),
+            (None, Some(Status::OutOfBounds(pc))) => format!("Stopped, jumped out of bounds to pc {}", pc),
+            (None, _) => format!("Terminated at pc {}", self.machine.pc)
+        };
+        format!("{} | {} | step {}", state, self.registers(), self.machine.steps)
+    }
+
+    fn list(&self) -> String {
+        let program = self.machine.program();
+        let pc = self.machine.pc;
+        let from = (pc - 3).max(0) as usize;
+        let to = ((pc + 4).max(0) as usize).min(program.len());
+
+        (from..to)
+            .map(|i| {
+                let marker = if i as isize == pc { "=>" } else if self.breakpoints.contains(&i) { " *" } else { "  " };
+                format!("{} {:>4}: {}", marker, i, program[i])
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::Op;
+
+    const PROGRAM: [Op; 9] = [
+        Op::Nop(0), Op::Acc(1), Op::Jmp(4), Op::Acc(3), Op::Jmp(-3), Op::Acc(-99), Op::Acc(1), Op::Jmp(-4), Op::Acc(6)
+    ];
+
+    fn debugger() -> Debugger<'static, Op> {
+        Debugger::new(Machine::new(&PROGRAM, 1), &["acc"])
+    }
+
+    #[test]
+    fn test_step_and_back() {
+        let mut debugger = debugger();
+        assert_eq!(debugger.command("s 2"), "pc 2: jmp +4 | acc = 1 | step 2");
+        assert_eq!(debugger.command("step"), "pc 6: acc +1 | acc = 1 | step 3");
+        assert_eq!(debugger.command("back 2"), "pc 1: acc +1 | acc = 0 | step 1");
+        assert_eq!(debugger.command("bk 5"), "At the start of the history\npc 0: nop +0 | acc = 0 | step 0");
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let mut debugger = debugger().with_history_limit(2);
+        assert_eq!(debugger.command("s 4"), "pc 7: jmp -4 | acc = 2 | step 4");
+        assert_eq!(debugger.history.len(), 2);
+        assert_eq!(debugger.command("bk 3"), "At the oldest state kept, the history holds 2 states\npc 2: jmp +4 | acc = 1 | step 2");
+
+        let mut debugger = self::debugger().with_history_limit(0);
+        assert_eq!(debugger.command("s"), "pc 1: acc +1 | acc = 0 | step 1");
+        assert_eq!(debugger.command("bk"), "At the oldest state kept, the history holds 0 states\npc 1: acc +1 | acc = 0 | step 1");
+    }
+
+    #[test]
+    fn test_continue() {
+        let mut debugger = debugger();
+        assert_eq!(debugger.command("break 4"), "Breakpoint at pc 4");
+        assert_eq!(debugger.command("c"), "Breakpoint at pc 4\npc 4: jmp -3 | acc = 5 | step 6");
+        assert_eq!(debugger.command("c"), "Loop detected, pc 1 is about to run again\npc 1: acc +1 | acc = 5 | step 7");
+
+        let mut debugger = self::debugger();
+        assert_eq!(debugger.command("watch acc"), "Watching acc = 0");
+        assert_eq!(debugger.command("continue"), "acc changed 0 -> 1\npc 2: jmp +4 | acc = 1 | step 2");
+        assert_eq!(debugger.command("watch x"), "Unknown register: x (registers: acc)");
+    }
+
+    #[test]
+    fn test_continue_conditional() {
+        // The jnz at pc 2 runs three times, each with a different value of b
+        let program = [Op::Set(1, 3), Op::Add(1, -1), Op::Jnz(1, -1), Op::Hlt];
+        let mut debugger = Debugger::new(Machine::new(&program, 2), &["a", "b"]);
+        assert_eq!(debugger.command("c"), "Terminated at pc 3 | a = 0, b = 0 | step 8");
+    }
+
+    #[test]
+    fn test_run() {
+        let mut out = vec![];
+        debugger().run("p\nbreak 99\nq\ns".as_bytes(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+            "pc 0: nop +0 | acc = 0 | step 0\n(debug) pc 0: nop +0 | acc = 0 | step 0\n(debug) Invalid pc: 99 (program has 9 instructions)\n(debug) \n");
+    }
+}
@@ -0,0 +1,431 @@
+use std::fmt;
+use std::hash::Hash;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Signed integers usable as vector components.
+pub trait Int:
+    Copy + Eq + Ord + Hash + fmt::Debug + fmt::Display + Default +
+    Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self> +
+    AddAssign + SubAssign + MulAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    fn abs(self) -> Self;
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {
+        $(impl Int for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+        })*
+    };
+}
+
+impl_int!(i8, i16, i32, i64, i128, isize);
+
+/// Component-wise operations needed by `BoundingBox`.
+pub trait Vector: Copy + Eq + Sub<Output = Self> {
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    /// Whether every component is less than or equal to that of `other`.
+    fn all_le(self, other: Self) -> bool;
+}
+
+macro_rules! vector {
+    ($name:ident, $($c:ident),+) => {
+        #[derive(PartialEq, Eq, Hash, Clone, Copy, Default, Debug)]
+        pub struct $name<T = i32> {
+            $(pub $c: T),+
+        }
+
+        impl<T: Int> $name<T> {
+            pub const fn new($($c: T),+) -> Self {
+                $name { $($c),+ }
+            }
+
+            pub fn zero() -> Self {
+                $name { $($c: T::ZERO),+ }
+            }
+
+            /// Vector with every component set to `v`.
+            pub fn splat(v: T) -> Self {
+                $name { $($c: v),+ }
+            }
+
+            /// Sum of the absolute components, i.e. the Manhattan distance to the origin.
+            pub fn manhattan(self) -> T {
+                T::ZERO $(+ self.$c.abs())+
+            }
+
+            /// Largest absolute component, i.e. the Chebyshev distance to the origin.
+            pub fn chebyshev(self) -> T {
+                let mut max = T::ZERO;
+                $(max = max.max(self.$c.abs());)+
+                max
+            }
+
+            pub fn manhattan_distance(self, other: Self) -> T {
+                (self - other).manhattan()
+            }
+
+            pub fn chebyshev_distance(self, other: Self) -> T {
+                (self - other).chebyshev()
+            }
+        }
+
+        impl<T: Int> Vector for $name<T> {
+            fn min(self, other: Self) -> Self {
+                $name { $($c: self.$c.min(other.$c)),+ }
+            }
+
+            fn max(self, other: Self) -> Self {
+                $name { $($c: self.$c.max(other.$c)),+ }
+            }
+
+            fn all_le(self, other: Self) -> bool {
+                true $(&& self.$c <= other.$c)+
+            }
+        }
+
+        impl<T: Int> Add for $name<T> {
+            type Output = Self;
+            fn add(self, other: Self) -> Self {
+                $name { $($c: self.$c + other.$c),+ }
+            }
+        }
+
+        impl<T: Int> Sub for $name<T> {
+            type Output = Self;
+            fn sub(self, other: Self) -> Self {
+                $name { $($c: self.$c - other.$c),+ }
+            }
+        }
+
+        impl<T: Int> Neg for $name<T> {
+            type Output = Self;
+            fn neg(self) -> Self {
+                $name { $($c: -self.$c),+ }
+            }
+        }
+
+        impl<T: Int> Mul<T> for $name<T> {
+            type Output = Self;
+            fn mul(self, by: T) -> Self {
+                $name { $($c: self.$c * by),+ }
+            }
+        }
+
+        impl<T: Int> AddAssign for $name<T> {
+            fn add_assign(&mut self, other: Self) {
+                *self = *self + other;
+            }
+        }
+
+        impl<T: Int> SubAssign for $name<T> {
+            fn sub_assign(&mut self, other: Self) {
+                *self = *self - other;
+            }
+        }
+
+        impl<T: Int> MulAssign<T> for $name<T> {
+            fn mul_assign(&mut self, by: T) {
+                *self = *self * by;
+            }
+        }
+
+        impl<T: fmt::Display> fmt::Display for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let components = [$(self.$c.to_string()),+];
+                write!(f, "({})", components.join(", "))
+            }
+        }
+    };
+}
+
+vector!(Vec2, x, y);
+vector!(Vec3, x, y, z);
+vector!(Vec4, x, y, z, w);
+
+/// Rotations are in screen coordinates, with y growing downwards, so turning right is clockwise.
+impl<T: Int> Vec2<T> {
+    pub fn rotate_right(self) -> Self {
+        Vec2::new(-self.y, self.x)
+    }
+
+    pub fn rotate_left(self) -> Self {
+        Vec2::new(self.y, -self.x)
+    }
+
+    /// Rotates clockwise by `degrees`, which must be a multiple of 90, negative meaning counterclockwise.
+    pub fn rotate(self, degrees: i32) -> Self {
+        assert!(degrees % 90 == 0, "Can only rotate in quarter turns, not {} degrees", degrees);
+        (0..(degrees.rem_euclid(360) / 90)).fold(self, |v, _| v.rotate_right())
+    }
+}
+
+impl<T> From<(T, T)> for Vec2<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Vec2 { x, y }
+    }
+}
+
+impl<T> From<Vec2<T>> for (T, T) {
+    fn from(v: Vec2<T>) -> Self {
+        (v.x, v.y)
+    }
+}
+
+/// Quarter turns around each axis, counterclockwise when looking from the positive side of the axis.
+impl<T: Int> Vec3<T> {
+    pub fn rotate_x(self) -> Self {
+        Vec3::new(self.x, -self.z, self.y)
+    }
+
+    pub fn rotate_y(self) -> Self {
+        Vec3::new(self.z, self.y, -self.x)
+    }
+
+    pub fn rotate_z(self) -> Self {
+        Vec3::new(-self.y, self.x, self.z)
+    }
+
+    /// The vector in all 24 orientations reachable by quarter turns, starting with itself.
+    pub fn orientations(self) -> Vec<Self> {
+        // Spin around x, then turn x to face each of the six directions
+        let facings: [fn(Self) -> Self; 6] = [
+            |v| v,
+            |v| v.rotate_y(),
+            |v| v.rotate_y().rotate_y(),
+            |v| v.rotate_y().rotate_y().rotate_y(),
+            |v| v.rotate_z(),
+            |v| v.rotate_z().rotate_z().rotate_z()
+        ];
+
+        let mut orientations = Vec::with_capacity(24);
+        let mut spun = self;
+        for _ in 0..4 {
+            orientations.extend(facings.iter().map(|face| face(spun)));
+            spun = spun.rotate_x();
+        }
+        orientations
+    }
+}
+
+impl<T> From<(T, T, T)> for Vec3<T> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Vec3 { x, y, z }
+    }
+}
+
+impl<T> From<(T, T, T, T)> for Vec4<T> {
+    fn from((x, y, z, w): (T, T, T, T)) -> Self {
+        Vec4 { x, y, z, w }
+    }
+}
+
+/// The four orthogonal directions, north being up.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Dir {
+    N, E, S, W
+}
+
+impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::N, Dir::E, Dir::S, Dir::W];
+
+    pub fn turn_right(self) -> Dir {
+        Dir::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Dir {
+        Dir::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn reverse(self) -> Dir {
+        Dir::ALL[(self as usize + 2) % 4]
+    }
+
+    /// Turns clockwise by `degrees`, which must be a multiple of 90.
+    pub fn turn(self, degrees: i32) -> Dir {
+        assert!(degrees % 90 == 0, "Can only turn in quarter turns, not {} degrees", degrees);
+        Dir::ALL[(self as usize + (degrees.rem_euclid(360) / 90) as usize) % 4]
+    }
+
+    /// Unit vector in screen coordinates, with y growing downwards.
+    pub fn vec<T: Int>(self) -> Vec2<T> {
+        match self {
+            Dir::N => Vec2::new(T::ZERO, -T::ONE),
+            Dir::E => Vec2::new(T::ONE, T::ZERO),
+            Dir::S => Vec2::new(T::ZERO, T::ONE),
+            Dir::W => Vec2::new(-T::ONE, T::ZERO)
+        }
+    }
+}
+
+/// The eight compass directions including diagonals, north being up.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Dir8 {
+    N, NE, E, SE, S, SW, W, NW
+}
+
+impl Dir8 {
+    pub const ALL: [Dir8; 8] = [Dir8::N, Dir8::NE, Dir8::E, Dir8::SE, Dir8::S, Dir8::SW, Dir8::W, Dir8::NW];
+
+    /// Turns clockwise by `degrees`, which must be a multiple of 45.
+    pub fn turn(self, degrees: i32) -> Dir8 {
+        assert!(degrees % 45 == 0, "Can only turn in eighths, not {} degrees", degrees);
+        Dir8::ALL[(self as usize + (degrees.rem_euclid(360) / 45) as usize) % 8]
+    }
+
+    pub fn turn_right(self) -> Dir8 {
+        self.turn(45)
+    }
+
+    pub fn turn_left(self) -> Dir8 {
+        self.turn(-45)
+    }
+
+    pub fn reverse(self) -> Dir8 {
+        self.turn(180)
+    }
+
+    /// Vector in screen coordinates, with y growing downwards.
+    pub fn vec<T: Int>(self) -> Vec2<T> {
+        let (z, o) = (T::ZERO, T::ONE);
+        let (x, y) = match self {
+            Dir8::N => (z, -o),
+            Dir8::NE => (o, -o),
+            Dir8::E => (o, z),
+            Dir8::SE => (o, o),
+            Dir8::S => (z, o),
+            Dir8::SW => (-o, o),
+            Dir8::W => (-o, z),
+            Dir8::NW => (-o, -o)
+        };
+        Vec2::new(x, y)
+    }
+}
+
+impl From<Dir> for Dir8 {
+    fn from(dir: Dir) -> Self {
+        Dir8::ALL[dir as usize * 2]
+    }
+}
+
+/// Smallest box containing a set of points, with both corners included.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct BoundingBox<V> {
+    pub min: V,
+    pub max: V
+}
+
+impl<V: Vector> BoundingBox<V> {
+    pub fn new(point: V) -> Self {
+        BoundingBox { min: point, max: point }
+    }
+
+    /// Box around all `points`, `None` if there are none.
+    pub fn from_points<I: IntoIterator<Item = V>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(BoundingBox::new(first), |bbox, p| bbox.include(p)))
+    }
+
+    /// The box grown to also contain `point`.
+    pub fn include(self, point: V) -> Self {
+        BoundingBox { min: self.min.min(point), max: self.max.max(point) }
+    }
+
+    pub fn contains(&self, point: V) -> bool {
+        self.min.all_le(point) && point.all_le(self.max)
+    }
+
+    /// `max - min`, one less than the number of points along each axis.
+    pub fn extent(&self) -> V {
+        self.max - self.min
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vec2_rotate() {
+        let (n, e, s, w) = (Dir::N.vec::<i32>(), Dir::E.vec(), Dir::S.vec(), Dir::W.vec());
+
+        // 90
+        assert_eq!(e.rotate(90), s);
+        assert_eq!(s.rotate(90), w);
+        assert_eq!(w.rotate(90), n);
+        assert_eq!(n.rotate(90), e);
+
+        // -90
+        assert_eq!(e.rotate(-90), n);
+        assert_eq!(n.rotate(-90), w);
+        assert_eq!(w.rotate(-90), s);
+        assert_eq!(s.rotate(-90), e);
+
+        // 270
+        assert_eq!(e.rotate(270), n);
+        assert_eq!(s.rotate(270), e);
+        assert_eq!(w.rotate(270), s);
+        assert_eq!(n.rotate(270), w);
+
+        // -270
+        assert_eq!(e.rotate(-270), s);
+        assert_eq!(s.rotate(-270), w);
+        assert_eq!(w.rotate(-270), n);
+        assert_eq!(n.rotate(-270), e);
+
+        // Waypoint
+        assert_eq!(Vec2::new(10, -4).rotate(90), Vec2::new(4, 10));
+        assert_eq!(Vec2::new(10, -4).rotate(180), Vec2::new(-10, 4));
+        assert_eq!(Vec2::new(10, -4).rotate(270), Vec2::new(-4, -10));
+        assert_eq!(Vec2::new(10, -4).rotate_left(), Vec2::new(10, -4).rotate(-90));
+    }
+
+    #[test]
+    fn test_ops_and_distances() {
+        let a = Vec3::new(1i64, -2, 3);
+        let b = Vec3::new(-4, 5, 6);
+        assert_eq!(a + b, Vec3::new(-3, 3, 9));
+        assert_eq!(a - b, Vec3::new(5, -7, -3));
+        assert_eq!(-a * 2, Vec3::new(-2, 4, -6));
+        assert_eq!(a.manhattan_distance(b), 15);
+        assert_eq!(a.chebyshev_distance(b), 7);
+        assert_eq!(Vec4::new(1, 2, 3, -4).manhattan(), 10);
+        assert_eq!(Vec2::new(3, -4).to_string(), "(3, -4)");
+    }
+
+    #[test]
+    fn test_orientations() {
+        let orientations = Vec3::new(1, 2, 3).orientations();
+        assert_eq!(orientations.len(), 24);
+        assert_eq!(orientations.iter().collect::<std::collections::HashSet<_>>().len(), 24);
+    }
+
+    #[test]
+    fn test_dir() {
+        assert_eq!(Dir::N.turn_right(), Dir::E);
+        assert_eq!(Dir::N.turn_left(), Dir::W);
+        assert_eq!(Dir::E.turn(-270), Dir::S);
+        assert_eq!(Dir::S.reverse(), Dir::N);
+        assert_eq!(Dir8::NW.turn_right(), Dir8::N);
+        assert_eq!(Dir8::from(Dir::W).turn(135), Dir8::NE);
+        assert_eq!(Dir8::SE.vec::<i32>(), Dir::S.vec() + Dir::E.vec());
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let bbox = BoundingBox::from_points(vec![Vec2::new(1, 5), Vec2::new(-2, 3), Vec2::new(4, -1)]).unwrap();
+        assert_eq!(bbox, BoundingBox { min: Vec2::new(-2, -1), max: Vec2::new(4, 5) });
+        assert!(bbox.contains(Vec2::new(0, 0)));
+        assert!(!bbox.contains(Vec2::new(5, 0)));
+        assert_eq!(bbox.extent(), Vec2::new(6, 6));
+        assert_eq!(BoundingBox::<Vec2>::from_points(vec![]), None);
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use crate::parse::ParseError;
+
+/// Offsets to the four orthogonal neighbours: up, right, down and left.
+pub const DIRS4: [(isize, isize); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Offsets to all eight neighbours, clockwise starting up.
+pub const DIRS8: [(isize, isize); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// What `Grid::get` returns for positions outside of the grid.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Edges<T> {
+    /// Nothing, the grid ends at its edges.
+    Bounded,
+    /// The cell on the other side, the grid repeats in every direction.
+    Wrapping,
+    /// The given value, the grid is surrounded by it without end.
+    Infinite(T)
+}
+
+/// A rectangular grid of cells stored row by row, indexed by `(x, y)` with `(0, 0)` at the top left.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    edges: Edges<T>
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T> where T: Clone {
+        Grid { width, height, cells: vec![fill; width * height], edges: Edges::Bounded }
+    }
+
+    /// Grid of `cells` given row by row, `cells.len()` must be a multiple of `width`.
+    pub fn from_cells(width: usize, cells: Vec<T>) -> Grid<T> {
+        assert!((width > 0 && cells.len().is_multiple_of(width)) || cells.is_empty(), "Cells don't fill rows of width {}", width);
+        let height = cells.len().checked_div(width).unwrap_or(0);
+        Grid { width, height, cells, edges: Edges::Bounded }
+    }
+
+    /// Parses one row per line with `f` mapping each char to a cell, `expected` describing the valid chars.
+    pub fn parse<F>(input: &str, expected: &str, f: F) -> Result<Grid<T>, ParseError> where F: Fn(char) -> Option<T> {
+        let mut width = None;
+        let mut cells = vec![];
+
+        for (i, line) in input.lines().enumerate() {
+            let row_start = cells.len();
+            for (idx, chr) in line.char_indices() {
+                let token = &line[idx..(idx + chr.len_utf8())];
+                if width.is_some_and(|w| cells.len() - row_start == w) {
+                    return Err(ParseError::at(line, &line[idx..], "end of row").with_line(i + 1));
+                }
+                cells.push(f(chr).ok_or_else(|| ParseError::at(line, token, expected).with_line(i + 1))?);
+            }
+
+            let row_width = cells.len() - row_start;
+            match width {
+                None => width = Some(row_width),
+                Some(w) if row_width < w => return Err(ParseError::at_end(line, expected).with_line(i + 1)),
+                _ => ()
+            }
+        }
+
+        Ok(Grid::from_cells(width.unwrap_or(0), cells))
+    }
+
+    pub fn with_edges(mut self, edges: Edges<T>) -> Grid<T> {
+        self.edges = edges;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn contains(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    #[inline]
+    fn index_of(&self, x: isize, y: isize) -> Option<usize> {
+        if self.contains(x, y) {
+            Some(y as usize * self.width + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Cell at `(x, y)`, or what is beyond the edges for positions outside of the grid.
+    #[inline]
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        match self.index_of(x, y) {
+            Some(idx) => Some(&self.cells[idx]),
+            None => match &self.edges {
+                Edges::Bounded => None,
+                Edges::Wrapping if self.cells.is_empty() => None,
+                Edges::Wrapping => {
+                    let x = x.rem_euclid(self.width as isize);
+                    let y = y.rem_euclid(self.height as isize);
+                    Some(&self.cells[y as usize * self.width + x as usize])
+                },
+                Edges::Infinite(outside) => Some(outside)
+            }
+        }
+    }
+
+    /// Cell at `(x, y)` if it is inside of the grid.
+    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        let idx = self.index_of(x, y)?;
+        Some(&mut self.cells[idx])
+    }
+
+    /// All cells row by row.
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// All positions and their cells row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((isize, isize), &T)> {
+        let width = self.width;
+        self.cells.iter()
+            .enumerate()
+            .map(move |(idx, cell)| (((idx % width) as isize, (idx / width) as isize), cell))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    #[inline]
+    fn neighbours<'a>(&'a self, x: isize, y: isize, dirs: &'a [(isize, isize)]) -> impl Iterator<Item = ((isize, isize), &'a T)> {
+        dirs.iter().filter_map(move |&(dx, dy)| {
+            let pos = (x + dx, y + dy);
+            self.get(pos.0, pos.1).map(|cell| (pos, cell))
+        })
+    }
+
+    /// The orthogonal neighbours of `(x, y)`.
+    pub fn neighbours4(&self, x: isize, y: isize) -> impl Iterator<Item = ((isize, isize), &T)> {
+        self.neighbours(x, y, &DIRS4)
+    }
+
+    /// The orthogonal and diagonal neighbours of `(x, y)`.
+    pub fn neighbours8(&self, x: isize, y: isize) -> impl Iterator<Item = ((isize, isize), &T)> {
+        self.neighbours(x, y, &DIRS8)
+    }
+
+    /// Walks from `(x, y)`, not included, in steps of `(dx, dy)` for as long as there are cells.
+    /// Never ends for grids that aren't bounded unless limited by the caller.
+    pub fn ray(&self, x: isize, y: isize, dx: isize, dy: isize) -> Ray<'_, T> {
+        Ray { grid: self, pos: (x, y), step: (dx, dy) }
+    }
+
+    /// Binary PPM image of the grid, each cell `scale` pixels wide and high in its `color`.
+    pub fn to_ppm<F>(&self, scale: usize, color: F) -> Vec<u8> where F: Fn(&T) -> [u8; 3] {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width * scale, self.height * scale).into_bytes();
+        for row in self.rows() {
+            let line = row.iter()
+                .flat_map(|cell| color(cell).repeat(scale))
+                .collect::<Vec<_>>();
+            for _ in 0..scale {
+                ppm.extend(&line);
+            }
+        }
+        ppm
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U> where F: Fn(&T) -> U {
+        let edges = match &self.edges {
+            Edges::Bounded => Edges::Bounded,
+            Edges::Wrapping => Edges::Wrapping,
+            Edges::Infinite(outside) => Edges::Infinite(f(outside))
+        };
+        Grid { width: self.width, height: self.height, cells: self.cells.iter().map(f).collect(), edges }
+    }
+}
+
+impl<T> Index<(isize, isize)> for Grid<T> {
+    type Output = T;
+    fn index(&self, (x, y): (isize, isize)) -> &T {
+        self.get(x, y).unwrap_or_else(|| panic!("({}, {}) is outside of the grid", x, y))
+    }
+}
+
+impl<T> IndexMut<(isize, isize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (isize, isize)) -> &mut T {
+        self.get_mut(x, y).unwrap_or_else(|| panic!("({}, {}) is outside of the grid", x, y))
+    }
+}
+
+/// Cells visited by `Grid::ray`.
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    pos: (isize, isize),
+    step: (isize, isize)
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = ((isize, isize), &'a T);
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pos = (self.pos.0 + self.step.0, self.pos.1 + self.step.1);
+        self.grid.get(self.pos.0, self.pos.1).map(|cell| (self.pos, cell))
+    }
+}
+
+/// Renders the cells row by row, each row on its own line.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn as_grid(s: &str) -> Grid<char> {
+        Grid::parse(s, "'.' or '#'", |c| Some(c).filter(|&c| c == '.' || c == '#')).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = as_grid("..#\n#..");
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 0)], '#');
+        assert_eq!(grid.to_string(), "..#\n#..\n");
+
+        let err = Grid::parse("..\n.x", "'.'", |c| Some(c).filter(|&c| c == '.')).unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 2: expected '.', found \"x\"");
+        assert!(Grid::parse("..\n.", "'.'", Some).is_err());
+        assert!(Grid::parse("..\n...", "'.'", Some).is_err());
+    }
+
+    #[test]
+    fn test_edges() {
+        let grid = as_grid("..#\n#..");
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(-1, 0), None);
+
+        let grid = grid.with_edges(Edges::Wrapping);
+        assert_eq!(grid.get(5, 0), Some(&'#'));
+        assert_eq!(grid.get(-3, -1), Some(&'#'));
+
+        let grid = grid.with_edges(Edges::Infinite('.'));
+        assert_eq!(grid.get(-100, 100), Some(&'.'));
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = as_grid("#.#\n.#.\n#.#");
+        assert_eq!(grid.neighbours4(1, 1).filter(|(_, &c)| c == '#').count(), 0);
+        assert_eq!(grid.neighbours8(1, 1).filter(|(_, &c)| c == '#').count(), 4);
+        assert_eq!(grid.neighbours8(0, 0).map(|(pos, _)| pos).collect::<Vec<_>>(), vec![(1, 0), (1, 1), (0, 1)]);
+    }
+
+    #[test]
+    fn test_ppm() {
+        let ppm = as_grid("#.").to_ppm(2, |&c| if c == '#' { [0, 0, 0] } else { [255, 255, 255] });
+        let (header, pixels) = ppm.split_at(11);
+        assert_eq!(header, b"P6\n4 2\n255\n");
+        assert_eq!(pixels, [[0; 6], [255; 6], [0; 6], [255; 6]].concat());
+    }
+
+    #[test]
+    fn test_ray() {
+        let grid = as_grid("#..\n...\n..#");
+        assert_eq!(grid.ray(0, 0, 1, 1).map(|(_, &c)| c).collect::<String>(), ".#");
+        assert_eq!(grid.ray(0, 0, -1, 0).count(), 0);
+
+        let grid = grid.with_edges(Edges::Wrapping);
+        assert_eq!(grid.ray(0, 0, 1, 0).take(4).map(|(pos, _)| pos).collect::<Vec<_>>(), vec![(1, 0), (2, 0), (3, 0), (4, 0)]);
+    }
+}
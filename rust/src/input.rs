@@ -0,0 +1,98 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Where the puzzle input for a day is read from.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InputSource {
+    /// A file at the given path.
+    File(PathBuf),
+    /// Standard input, given as `-` on the command line.
+    Stdin,
+    /// The input itself, e.g. passed as a command line argument.
+    Inline(String),
+    /// `dayNN` in `input_dir()`.
+    Default(u32)
+}
+
+impl InputSource {
+    /// Source for an input argument, `-` meaning stdin and anything else a file path.
+    pub fn from_arg(arg: &str) -> Self {
+        match arg {
+            "-" => InputSource::Stdin,
+            path => InputSource::File(PathBuf::from(path))
+        }
+    }
+
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            InputSource::File(path) => fs::read_to_string(path),
+            InputSource::Stdin => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                Ok(input)
+            },
+            InputSource::Inline(input) => Ok(input.clone()),
+            InputSource::Default(day) => fs::read_to_string(default_path(*day))
+        }
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::File(path) => write!(f, "{}", path.display()),
+            InputSource::Stdin => write!(f, "<stdin>"),
+            InputSource::Inline(_) => write!(f, "<inline input>"),
+            InputSource::Default(day) => write!(f, "{}", default_path(*day).display())
+        }
+    }
+}
+
+/// The directory of the inputs: `AOC_INPUT_DIR` if set, else `input` in the workspace root
+/// the binary was built in, else `input` in the working directory for binaries moved elsewhere.
+pub fn input_dir() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    resolve_input_dir(env::var_os("AOC_INPUT_DIR"), manifest_dir.parent().unwrap_or(manifest_dir).join("input"))
+}
+
+fn resolve_input_dir(var: Option<OsString>, built: PathBuf) -> PathBuf {
+    match var {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ if built.is_dir() => built,
+        _ => PathBuf::from("input")
+    }
+}
+
+pub fn default_path(day: u32) -> PathBuf {
+    input_dir().join(format!("day{:02}", day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_arg() {
+        assert_eq!(InputSource::from_arg("-"), InputSource::Stdin);
+        assert_eq!(InputSource::from_arg("in.txt"), InputSource::File(PathBuf::from("in.txt")));
+    }
+
+    #[test]
+    fn test_read() {
+        assert_eq!(InputSource::Inline("1\n2".to_string()).read().unwrap(), "1\n2");
+        assert!(default_path(8).ends_with("input/day08"));
+        assert!(InputSource::File(PathBuf::from("no/such/file")).read().is_err());
+    }
+
+    #[test]
+    fn test_input_dir() {
+        let built = Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf();
+        assert_eq!(resolve_input_dir(Some("elsewhere".into()), built.clone()), PathBuf::from("elsewhere"));
+        assert_eq!(resolve_input_dir(Some("".into()), built.clone()), built);
+        assert_eq!(resolve_input_dir(None, built.join("no/such/dir")), PathBuf::from("input"));
+    }
+}
@@ -1,34 +1,133 @@
-extern crate time;
-
-use time::*;
-
-pub fn measure<F>(f: F) where F: Fn() -> () {
-    let start = precise_time_ns();
-    let mut _times = 100;
-
-    #[cfg(feature = "timeit")] {
-        f();
-        let dur_ns = precise_time_ns() - start;
-        if dur_ns > 500_000_000 {
-            _times /= 10;
-        } else if dur_ns < 500_000 {
-            _times *= 10;
-        }
-        for _ in 0..(_times - 1) {
-            f();
-        }
-    }
-    #[cfg(not(feature = "timeit"))] {
-        f();
-    }
+use std::fmt::Display;
+use std::hint::black_box;
+
+pub mod parse;
+pub use parse::ParseError;
+pub mod input;
+pub use input::InputSource;
+pub mod output;
+pub use output::Format;
+pub mod verify;
+pub mod measure;
+pub use measure::{BenchConfig, Stats};
+pub mod baseline;
+pub use baseline::Baseline;
+pub mod grid;
+pub use grid::Grid;
+pub mod geom;
+pub mod vm;
+pub mod debugger;
+pub use verify::{KnownAnswers, Verdict};
 
-    let dur_ns = precise_time_ns() - start;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+
+pub trait Solution {
+    const DAY: u32;
+    type Input;
+    type Part1: Display;
+    type Part2: Display;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+    fn part1(input: &Self::Input) -> Self::Part1;
+    fn part2(input: &Self::Input) -> Self::Part2;
+}
 
-    #[cfg(feature = "timeit")] {
-        println!("It took: {}ms on average for {} times", (dur_ns / _times as u64) as f64 / 1_000_000.0, _times);
+#[derive(Debug)]
+pub struct Answers {
+    pub part1: String,
+    pub part2: String
+}
+
+/// Answers together with timings of parsing and each part.
+#[derive(Debug)]
+pub struct Report {
+    pub day: u32,
+    pub answers: Answers,
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+    pub input_md5: String
+}
+
+impl Report {
+    /// Median time of parsing and both parts in nanoseconds.
+    pub fn total_ns(&self) -> f64 {
+        self.parse.median + self.part1.median + self.part2.median
     }
-    #[cfg(not(feature = "timeit"))] {
-        println!("It took: {}ms", dur_ns as f64 / 1_000_000.0);
+}
+
+pub struct Day {
+    pub day: u32,
+    pub run: fn(&str) -> Result<Answers, ParseError>,
+    pub timed: fn(&str, &BenchConfig) -> Result<Report, ParseError>
+}
+
+impl Day {
+    pub const fn of<S: Solution>() -> Day {
+        Day { day: S::DAY, run: solve::<S>, timed: solve_timed::<S> }
     }
+}
+
+pub static DAYS: &[Day] = &[
+    Day::of::<day01::Day01>(),
+    Day::of::<day02::Day02>(),
+    Day::of::<day03::Day03>(),
+    Day::of::<day04::Day04>(),
+    Day::of::<day05::Day05>(),
+    Day::of::<day06::Day06>(),
+    Day::of::<day07::Day07>(),
+    Day::of::<day08::Day08>(),
+    Day::of::<day09::Day09>(),
+    Day::of::<day10::Day10>(),
+    Day::of::<day11::Day11>(),
+    Day::of::<day12::Day12>(),
+    Day::of::<day13::Day13>(),
+    Day::of::<day14::Day14>(),
+    Day::of::<day15::Day15>(),
+    Day::of::<day16::Day16>(),
+];
+
+pub fn find_day(day: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.day == day)
+}
+
+pub fn solve<S: Solution>(input: &str) -> Result<Answers, ParseError> {
+    let input = S::parse(input).map_err(|e| e.with_day(S::DAY))?;
+    Ok(Answers {
+        part1: S::part1(&input).to_string(),
+        part2: S::part2(&input).to_string()
+    })
+}
+
+/// Like `solve` but benchmarking parsing and each part separately according to `config`.
+pub fn solve_timed<S: Solution>(input: &str, config: &BenchConfig) -> Result<Report, ParseError> {
+    // Inputs go through black_box so work on them isn't hoisted out of the timed calls
+    let (parsed, parse) = measure::bench(config, || S::parse(black_box(input)));
+    let parsed = parsed.map_err(|e| e.with_day(S::DAY))?;
+    let (part1, part1_stats) = measure::bench(config, || S::part1(black_box(&parsed)));
+    let (part2, part2_stats) = measure::bench(config, || S::part2(black_box(&parsed)));
 
+    Ok(Report {
+        day: S::DAY,
+        answers: Answers { part1: part1.to_string(), part2: part2.to_string() },
+        parse,
+        part1: part1_stats,
+        part2: part2_stats,
+        input_md5: format!("{:x}", md5::compute(input))
+    })
 }
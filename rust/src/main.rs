@@ -0,0 +1,655 @@
+extern crate utils;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use rayon::prelude::*;
+use utils::*;
+use utils::baseline::Change;
+use utils::geom::Vec2;
+
+const USAGE: &str =
+"Usage: aoc run [--format text|json|csv] [--parallel] [--threads <n>]
+               [--bench [--baseline <file>] [--save-baseline]] <days> [input | - | --text <input>]
+       aoc verify [--answers <file>] [days]
+       aoc debug 8 [input]
+       aoc trace [--format text|json] 8 [input | - | --text <input>]
+       aoc disasm [--dot | --labels] 8 [input | - | --text <input>]
+       aoc asm 8 [input | - | --text <input>]
+       aoc ksum [--k <k>] [--target <sum>] [--all [--repeats indices|values]]
+                [input | - | --text <input>]
+       aoc passwords [--policy <policy>] [input | - | --text <input>]
+       aoc audit [--format text|json] [input | - | --text <input>]
+       aoc slopes [--slopes <slopes> | --slopes-file <file> | --search <right>,<down> [--most]]
+                  [input | - | --text <input>]
+       aoc path [--ppm <file>] <right>,<down> [input | - | --text <input>]
+       aoc passports [--schema <file>] [--format text|json] [input | - | --text <input>]
+
+  <days>          a single day (14), a range (3..9 or 3..=9) or all
+  [input]         input file, only when running a single day (default: input/dayNN,
+                  or dayNN in $AOC_INPUT_DIR if set)
+  -               read the input from stdin
+  --text <input>  use the given text as input
+  --format        text (default), one JSON object per line or CSV with a header row
+  --parallel      run the days in parallel, one thread per CPU, reporting in day order,
+                  not with --bench
+  --threads <n>   run the days in parallel on n threads
+  --bench         benchmark parsing and each part with warm-up and repeated samples,
+                  default when built with the timeit feature
+  --baseline      benchmark results to compare with (default: bench-baseline.csv),
+                  flagging significant slowdowns
+  --save-baseline save the benchmark results of the days run to the baseline
+
+verify runs the given days (default: all) on their default inputs and compares
+the answers with the known ones in --answers (default: input/answers.toml)
+
+debug runs the day 08 console program in an interactive debugger reading
+commands from stdin, type help for a list of them
+
+trace runs the day 08 console program until it ends or loops, listing every
+instruction run with the accumulator after it, and the instructions of the loop
+
+disasm lists the day 08 console program by basic block, marking jumps, their
+targets, unreachable code and the loop, or with --dot prints its control-flow
+graph for Graphviz, or with --labels as source for asm with labels for jumps
+
+asm assembles a day 08 console program written with labels (name:), labels as
+jmp and nop arguments and # comments into the puzzle's nop/acc/jmp +N format
+
+ksum finds k (default 2) of the day 01 entries summing to the target (default 2020),
+or with --all every combination of them, telling repeated values apart by their indices
+or counting each combination of values once (default)
+
+passwords checks the day 02 passwords against --policy (default: count), listing the
+rows breaking it and why. Policies are count, positions, regex(\"<pattern>\"),
+entropy(<bits>), forbid(\"<substring>\", ...), and(<policy>, ...), or(<policy>, ...)
+and not(<policy>), e.g. and(positions, not(regex(\"^[aeiou]\")))
+
+audit summarises the day 02 password database: rows following each policy, ranges,
+policy characters, rows following only one of the policies and positions past the
+end of the password
+
+slopes counts the trees hit on the day 03 map for each slope, given as right,down
+separated by spaces or lines (default: those of part 2), negative right going left.
+--search ranks all slopes up to the given steps left or right and down, fewest trees
+first or with --most most trees first
+
+path prints the day 03 map, repeated as far as needed, with the path down it by the
+slope marked O on open squares and X on trees hit, or with --ppm writes it as an image
+
+passports checks the day 04 records against the fields of --schema (default: those
+of the puzzle), see src/day04.rs for the format, and lists the missing required fields,
+invalid values, unknown fields and duplicate keys of each record with any";
+
+fn parse_days(spec: &str) -> Result<Vec<u32>, String> {
+    fn parse_day(s: &str) -> Result<u32, String> {
+        s.trim().parse::<u32>().map_err(|_| format!("Invalid day: {}", s))
+    }
+
+    let days: Vec<u32> =
+        if spec == "all" {
+            DAYS.iter().map(|d| d.day).collect()
+        } else if let Some(idx) = spec.find("..=") {
+            (parse_day(&spec[..idx])?..=parse_day(&spec[(idx + 3)..])?).collect()
+        } else if let Some(idx) = spec.find("..") {
+            (parse_day(&spec[..idx])?..parse_day(&spec[(idx + 2)..])?).collect()
+        } else {
+            vec![parse_day(spec)?]
+        };
+
+    if days.is_empty() {
+        return Err(format!("No days in range: {}", spec));
+    }
+
+    Ok(days)
+}
+
+/// Maps `f` over `items`, on a pool of `threads` if given, in order either way.
+/// In parallel all items are done before returning, otherwise each is done as it's iterated.
+fn run_in_order<'a, T, R, F>(items: &'a [T], threads: Option<usize>, f: F) -> Result<Box<dyn Iterator<Item = R> + 'a>, String>
+    where T: Sync, R: Send + 'a, F: Fn(&T) -> R + Send + Sync + 'a {
+    match threads {
+        Some(threads) => {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(|e| format!("Failed to start {} threads: {}", threads, e))?;
+            Ok(Box::new(pool.install(|| items.par_iter().map(f).collect::<Vec<_>>()).into_iter()))
+        },
+        None => Ok(Box::new(items.iter().map(f)))
+    }
+}
+
+fn run(days_spec: &str, options: &Options) -> Result<(), String> {
+    let days = parse_days(days_spec)?
+        .into_iter()
+        .map(|day| find_day(day).ok_or(format!("No solution for day {:02}", day)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if options.source.is_some() && days.len() > 1 {
+        return Err("An input can only be given when running a single day".to_string());
+    }
+    if options.save_baseline && !options.bench {
+        return Err("A baseline can only be saved when benchmarking with --bench".to_string());
+    }
+    if options.bench && options.threads.is_some() {
+        return Err("Benchmarks can't be run in parallel, the days would compete for the CPU".to_string());
+    }
+
+    let bench = if options.bench { BenchConfig::default() } else { BenchConfig::once() };
+    let baseline_file = options.baseline_file.clone().unwrap_or_else(Baseline::default_path);
+    let mut baseline = if options.bench { Baseline::load(&baseline_file)? } else { Baseline::default() };
+    let compare = !baseline.is_empty();
+    let mut slower = 0;
+
+    if options.format == Format::Csv {
+        println!("{}", Report::csv_header());
+    }
+
+    let run_day = |day: &&Day| -> Result<Report, String> {
+        let source = options.source.clone().unwrap_or(InputSource::Default(day.day));
+        let input = source.read()
+            .map_err(|e| format!("Failed to read {}: {}", source, e))?;
+
+        (day.timed)(&input, &bench)
+            .map_err(|e| format!("Failed to parse {}: {}", source, e))
+    };
+
+    let reports = run_in_order(&days, options.threads, run_day)?;
+
+    for (i, (day, report)) in days.iter().zip(reports).enumerate() {
+        let report = report?;
+
+        match options.format {
+            Format::Text => {
+                if i > 0 {
+                    println!();
+                }
+                println!("{}", report.to_text());
+            },
+            Format::Json => println!("{}", report.to_json()),
+            Format::Csv => println!("{}", report.to_csv())
+        }
+
+        if let Some(comparisons) = baseline.compare(&report).filter(|_| compare) {
+            // Keep stdout machine-readable for the other formats
+            let print = |line: String| if options.format == Format::Text { println!("{}", line) } else { eprintln!("{}", line) };
+            print(format!("Day {:02} compared to baseline:", day.day));
+            for comparison in &comparisons {
+                if comparison.change == Change::Slower {
+                    slower += 1;
+                }
+                print(format!("  {}", comparison));
+            }
+        } else if compare && baseline.input_md5(day.day).is_some_and(|md5| md5 != report.input_md5) {
+            eprintln!("Day {:02} baseline was recorded on a different input, not compared", day.day);
+        }
+
+        if options.save_baseline {
+            baseline.update(&report);
+        }
+    }
+
+    if compare && slower > 0 {
+        eprintln!("\nSignificant slowdowns compared to {}: {}", baseline_file.display(), slower);
+    }
+    if options.save_baseline {
+        baseline.save(&baseline_file)
+            .map_err(|e| format!("Failed to write {}: {}", baseline_file.display(), e))?;
+        eprintln!("Saved baseline to {}", baseline_file.display());
+    }
+
+    Ok(())
+}
+
+fn verify(days_spec: &str, answers_file: Option<PathBuf>) -> Result<(), String> {
+    let answers_file = answers_file.unwrap_or_else(KnownAnswers::default_path);
+    let known = fs::read_to_string(&answers_file)
+        .map_err(|e| format!("Failed to read {}: {}", answers_file.display(), e))
+        .and_then(|s| KnownAnswers::parse(&s).map_err(|e| format!("Failed to parse {}: {}", answers_file.display(), e)))?;
+    let days = parse_days(days_spec)?;
+
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    for day in days {
+        let answers = find_day(day)
+            .ok_or(format!("No solution for day {:02}", day))
+            .and_then(|d| {
+                let source = InputSource::Default(day);
+                let input = source.read().map_err(|e| format!("Failed to read {}: {}", source, e))?;
+                (d.run)(&input).map_err(|e| format!("Failed to parse {}: {}", source, e))
+            });
+
+        let answers = match answers {
+            Ok(answers) => answers,
+            Err(e) => {
+                println!("Day {:02}: FAIL ({})", day, e);
+                failed += 2;
+                continue;
+            }
+        };
+
+        let verdicts = [
+            known.verify(day, 1, &answers.part1),
+            known.verify(day, 2, &answers.part2)
+        ];
+        for verdict in &verdicts {
+            match verdict {
+                Verdict::Pass => passed += 1,
+                Verdict::Fail { .. } => failed += 1,
+                Verdict::Missing => missing += 1
+            }
+        }
+        println!("Day {:02}: part1 {}, part2 {}", day, verdicts[0], verdicts[1]);
+    }
+
+    println!();
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+
+    if failed > 0 {
+        Err(format!("Verification against {} failed", answers_file.display()))
+    } else {
+        Ok(())
+    }
+}
+
+type ConsoleParser = fn(&str) -> Result<Vec<day08::Instruction>, ParseError>;
+
+/// Reads the day 08 console program, the only day with a console, and parses it with `parse`.
+fn console_program(day: &str, source: &InputSource, what: &str, parse: ConsoleParser) -> Result<Vec<day08::Instruction>, String> {
+    if parse_days(day)? != [8] {
+        return Err(format!("Only the console of day 08 can be {}", what));
+    }
+
+    let input = source.read().map_err(|e| format!("Failed to read {}: {}", source, e))?;
+    parse(&input).map_err(|e| format!("Failed to parse {}: {}", source, e))
+}
+
+fn debug(day: &str, source: Option<InputSource>) -> Result<(), String> {
+    let source = source.unwrap_or(InputSource::Default(8));
+    if source == InputSource::Stdin {
+        return Err("The debugger reads commands from stdin, the input must be given otherwise".to_string());
+    }
+    let program = console_program(day, &source, "debugged", day08::Day08::parse)?;
+
+    let stdin = io::stdin();
+    day08::debugger(&program)
+        .run(stdin.lock(), io::stdout())
+        .map_err(|e| e.to_string())
+}
+
+fn trace(day: &str, options: &Options) -> Result<(), String> {
+    let program = console_program(day, options.source.as_ref().unwrap_or(&InputSource::Default(8)), "traced", day08::Day08::parse)?;
+    let trace = day08::trace(&program);
+
+    match options.format {
+        Format::Text => println!("{}", trace.to_text(&program, day08::REGISTERS)),
+        Format::Json => println!("{}", trace.to_json(day08::REGISTERS)),
+        Format::Csv => return Err("Traces can only be shown as text or JSON".to_string())
+    }
+    Ok(())
+}
+
+fn disassemble(day: &str, options: &Options) -> Result<(), String> {
+    let program = console_program(day, options.source.as_ref().unwrap_or(&InputSource::Default(8)), "disassembled", day08::Day08::parse)?;
+    let control_flow = day08::ControlFlow::new(&program);
+
+    if options.dot {
+        println!("{}", control_flow.to_dot());
+    } else if options.labels {
+        println!("{}", day08::to_assembly(&program));
+    } else {
+        println!("{}", control_flow.disassemble());
+    }
+    Ok(())
+}
+
+fn k_sum(options: &Options) -> Result<(), String> {
+    let source = options.source.clone().unwrap_or(InputSource::Default(1));
+    let input = source.read().map_err(|e| format!("Failed to read {}: {}", source, e))?;
+    let entries = day01::Day01::parse(&input).map_err(|e| format!("Failed to parse {}: {}", source, e))?;
+    let (k, target) = (options.k.unwrap_or(2), options.target.unwrap_or(2020));
+
+    let terms = |entries: &mut dyn Iterator<Item = i32>| entries.map(|e| e.to_string()).collect::<Vec<_>>().join(" + ");
+
+    if !options.all {
+        let combination = day01::k_sum(&entries, k, target).ok_or(format!("No {} entries sum to {}", k, target))?;
+        println!("{} = {}", terms(&mut combination.iter().copied()), target);
+        match day01::product(&combination) {
+            Some(product) => println!("Product: {}", product),
+            None => println!("Product: overflows")
+        }
+        return Ok(());
+    }
+
+    let combinations = day01::k_sums(&entries, k, target, options.repeats);
+    if combinations.is_empty() {
+        return Err(format!("No {} entries sum to {}", k, target));
+    }
+    for combination in &combinations {
+        let indices = combination.iter().map(|idx| idx.to_string()).collect::<Vec<_>>();
+        println!("Indices {}: {} = {}", indices.join(", "), terms(&mut combination.iter().map(|&idx| entries[idx])), target);
+    }
+    println!("{} {}", combinations.len(), if combinations.len() == 1 { "combination" } else { "combinations" });
+    Ok(())
+}
+
+fn passwords(options: &Options) -> Result<(), String> {
+    let source = options.source.clone().unwrap_or(InputSource::Default(2));
+    let input = source.read().map_err(|e| format!("Failed to read {}: {}", source, e))?;
+    let rows = day02::Day02::parse(&input).map_err(|e| format!("Failed to parse {}: {}", source, e))?;
+    let policy = day02::parse_policy(options.policy.as_deref().unwrap_or("count"))
+        .map_err(|e| format!("Invalid policy: {}", e))?;
+
+    let mut conforming = 0;
+    for (i, row) in rows.iter().enumerate() {
+        match policy.check(row) {
+            Ok(()) => conforming += 1,
+            Err(broken) => println!("line {}: {}: {}", i + 1, row, broken)
+        }
+    }
+    println!("{} of {} passwords follow {}", conforming, rows.len(), policy);
+    Ok(())
+}
+
+fn audit(options: &Options) -> Result<(), String> {
+    let source = options.source.clone().unwrap_or(InputSource::Default(2));
+    let input = source.read().map_err(|e| format!("Failed to read {}: {}", source, e))?;
+    let rows = day02::Day02::parse(&input).map_err(|e| format!("Failed to parse {}: {}", source, e))?;
+    let audit = day02::Audit::new(&rows);
+
+    match options.format {
+        Format::Text => println!("{}", audit.to_text()),
+        Format::Json => println!("{}", audit.to_json()),
+        Format::Csv => return Err("Audits can only be shown as text or JSON".to_string())
+    }
+    Ok(())
+}
+
+fn slopes(options: &Options) -> Result<(), String> {
+    let source = options.source.clone().unwrap_or(InputSource::Default(3));
+    let input = source.read().map_err(|e| format!("Failed to read {}: {}", source, e))?;
+    let map = day03::Day03::parse(&input).map_err(|e| format!("Failed to parse {}: {}", source, e))?;
+    let describe = |slope: Vec2<isize>, trees: usize| format!("right {}, down {}: {} trees", slope.x, slope.y, trees);
+
+    if let Some(bounds) = &options.search {
+        let bounds = day03::parse_slope(bounds).map_err(|e| format!("Invalid search bounds: {}", e))?;
+        let ranking = day03::rank_slopes(&map, bounds.x.abs(), bounds.x.abs(), bounds.y, options.most);
+        for (rank, (slope, trees)) in ranking.into_iter().enumerate() {
+            println!("{:>4}. {}", rank + 1, describe(slope, trees));
+        }
+        return Ok(());
+    }
+
+    let slopes = match (&options.slopes, &options.slopes_file) {
+        (Some(slopes), _) => day03::parse_slopes(slopes).map_err(|e| format!("Invalid slopes: {}", e))?,
+        (None, Some(file)) => fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))
+            .and_then(|s| day03::parse_slopes(&s).map_err(|e| format!("Failed to parse {}: {}", file.display(), e)))?,
+        (None, None) => day03::SLOPES.to_vec()
+    };
+
+    let mut product = 1u128;
+    for slope in slopes {
+        let trees = day03::num_trees_hit(&map, slope);
+        product *= trees as u128;
+        println!("{}", describe(slope, trees));
+    }
+    println!("Product: {}", product);
+    Ok(())
+}
+
+/// Pixels per square of the map in PPM images of the path.
+const PPM_SCALE: usize = 4;
+
+fn path(slope: &str, options: &Options) -> Result<(), String> {
+    let source = options.source.clone().unwrap_or(InputSource::Default(3));
+    let input = source.read().map_err(|e| format!("Failed to read {}: {}", source, e))?;
+    let map = day03::Day03::parse(&input).map_err(|e| format!("Failed to parse {}: {}", source, e))?;
+    let slope = day03::parse_slope(slope).map_err(|e| format!("Invalid slope: {}", e))?;
+    let rendered = day03::render(&map, slope);
+
+    match &options.ppm_file {
+        Some(file) => {
+            fs::write(file, rendered.to_ppm(PPM_SCALE, |r| r.color()))
+                .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+            eprintln!("Wrote {}x{} squares to {}", rendered.width(), rendered.height(), file.display());
+        },
+        None => print!("{}", rendered)
+    }
+    println!("{} trees hit", day03::num_trees_hit(&map, slope));
+    Ok(())
+}
+
+fn passports(options: &Options) -> Result<(), String> {
+    let source = options.source.clone().unwrap_or(InputSource::Default(4));
+    let input = source.read().map_err(|e| format!("Failed to read {}: {}", source, e))?;
+    let records = day04::Day04::parse(&input).map_err(|e| format!("Failed to parse {}: {}", source, e))?;
+    let schema = match &options.schema_file {
+        Some(file) => fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))
+            .and_then(|s| day04::Schema::parse(&s).map_err(|e| format!("Failed to parse {}: {}", file.display(), e)))?,
+        None => day04::Schema::parse(day04::DEFAULT_SCHEMA).map_err(|e| e.to_string())?
+    };
+
+    let report = day04::Report::new(&records, &schema);
+
+    match options.format {
+        Format::Text => println!("{}", report.to_text()),
+        Format::Json => println!("{}", report.to_json()),
+        Format::Csv => return Err("Passport reports can only be shown as text or JSON".to_string())
+    }
+    Ok(())
+}
+
+fn assemble(day: &str, options: &Options) -> Result<(), String> {
+    let program = console_program(day, options.source.as_ref().unwrap_or(&InputSource::Default(8)), "assembled", day08::assemble)?;
+    for instruction in program {
+        println!("{}", instruction);
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+struct Options {
+    positional: Vec<String>,
+    source: Option<InputSource>,
+    format: Format,
+    answers_file: Option<PathBuf>,
+    bench: bool,
+    baseline_file: Option<PathBuf>,
+    save_baseline: bool,
+    /// Print a disassembly as a Graphviz graph
+    dot: bool,
+    /// Print a disassembly with labels for jumps
+    labels: bool,
+    /// Number of entries to sum for ksum
+    k: Option<usize>,
+    /// Sum to find for ksum
+    target: Option<i64>,
+    /// Find every combination for ksum
+    all: bool,
+    /// How ksum tells repeated values apart
+    repeats: day01::Repeats,
+    /// Policy for passwords to check
+    policy: Option<String>,
+    /// Slopes to count trees for
+    slopes: Option<String>,
+    slopes_file: Option<PathBuf>,
+    /// Bounds of the slopes to rank
+    search: Option<String>,
+    /// Rank slopes hitting the most trees first
+    most: bool,
+    /// Image to write the rendered path to
+    ppm_file: Option<PathBuf>,
+    /// Fields of the records to check
+    schema_file: Option<PathBuf>,
+    /// Threads to run days in parallel on, if any
+    threads: Option<usize>
+}
+
+fn parse_options<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        positional: vec![],
+        source: None,
+        format: Format::Text,
+        answers_file: None,
+        bench: cfg!(feature = "timeit"),
+        baseline_file: None,
+        save_baseline: false,
+        dot: false,
+        labels: false,
+        k: None,
+        target: None,
+        all: false,
+        repeats: day01::Repeats::Values,
+        policy: None,
+        slopes: None,
+        slopes_file: None,
+        search: None,
+        most: false,
+        ppm_file: None,
+        schema_file: None,
+        threads: None
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--text" => options.source = Some(InputSource::Inline(args.next().ok_or(USAGE)?)),
+            "--format" => options.format = args.next().ok_or(USAGE)?.parse()?,
+            "--bench" => options.bench = true,
+            "--baseline" => options.baseline_file = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--save-baseline" => options.save_baseline = true,
+            "--dot" => options.dot = true,
+            "--labels" => options.labels = true,
+            "--k" => {
+                let k = args.next().ok_or(USAGE)?;
+                options.k = Some(k.parse().map_err(|_| format!("Invalid k: {}", k))?);
+            },
+            "--policy" => options.policy = Some(args.next().ok_or(USAGE)?),
+            "--slopes" => options.slopes = Some(args.next().ok_or(USAGE)?),
+            "--slopes-file" => options.slopes_file = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--search" => options.search = Some(args.next().ok_or(USAGE)?),
+            "--most" => options.most = true,
+            "--schema" => options.schema_file = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--ppm" => options.ppm_file = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--all" => options.all = true,
+            "--repeats" => options.repeats = args.next().ok_or(USAGE)?.parse()?,
+            "--target" => {
+                let target = args.next().ok_or(USAGE)?;
+                options.target = Some(target.parse().map_err(|_| format!("Invalid target: {}", target))?);
+            },
+            "--parallel" => options.threads = options.threads.or_else(|| Some(num_cpus::get())),
+            "--threads" => {
+                let threads = args.next().ok_or(USAGE)?;
+                match threads.parse::<usize>() {
+                    Ok(n) if n > 0 => options.threads = Some(n),
+                    _ => return Err(format!("Invalid number of threads: {}", threads))
+                }
+            },
+            "--answers" => options.answers_file = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            _ => options.positional.push(arg)
+        }
+    }
+
+    Ok(options)
+}
+
+fn main() {
+    let result = parse_options(env::args().skip(1)).and_then(|options| {
+        match options.positional.iter().map(|a| a.as_str()).collect::<Vec<_>>().as_slice() {
+            ["run", days] => run(days, &options),
+            ["run", days, input] if options.source.is_none() =>
+                run(days, &Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["debug", day] => debug(day, options.source.clone()),
+            ["debug", day, input] if options.source.is_none() => debug(day, Some(InputSource::from_arg(input))),
+            ["trace", day] => trace(day, &options),
+            ["trace", day, input] if options.source.is_none() =>
+                trace(day, &Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["disasm", day] => disassemble(day, &options),
+            ["disasm", day, input] if options.source.is_none() =>
+                disassemble(day, &Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["asm", day] => assemble(day, &options),
+            ["asm", day, input] if options.source.is_none() =>
+                assemble(day, &Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["ksum"] => k_sum(&options),
+            ["ksum", input] if options.source.is_none() =>
+                k_sum(&Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["passwords"] => passwords(&options),
+            ["passwords", input] if options.source.is_none() =>
+                passwords(&Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["audit"] => audit(&options),
+            ["audit", input] if options.source.is_none() =>
+                audit(&Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["slopes"] => slopes(&options),
+            ["slopes", input] if options.source.is_none() =>
+                slopes(&Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["path", slope] => path(slope, &options),
+            ["path", slope, input] if options.source.is_none() =>
+                path(slope, &Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["passports"] => passports(&options),
+            ["passports", input] if options.source.is_none() =>
+                passports(&Options { source: Some(InputSource::from_arg(input)), ..options.clone() }),
+            ["verify"] => verify("all", options.answers_file),
+            ["verify", days] => verify(days, options.answers_file),
+            _ => Err(USAGE.to_string())
+        }
+    });
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(parse_days("14"), Ok(vec![14]));
+        assert_eq!(parse_days("3..6"), Ok(vec![3, 4, 5]));
+        assert_eq!(parse_days("3..=6"), Ok(vec![3, 4, 5, 6]));
+        assert_eq!(parse_days("all"), Ok((1..=16).collect()));
+        assert!(parse_days("6..3").is_err());
+        assert!(parse_days("x").is_err());
+    }
+
+    fn options(args: &[&str]) -> Result<Options, String> {
+        parse_options(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let parsed = options(&["run", "--threads", "3", "all"]).unwrap();
+        assert_eq!(parsed.threads, Some(3));
+        assert_eq!(parsed.positional, vec!["run", "all"]);
+        assert_eq!(options(&["--parallel"]).unwrap().threads, Some(num_cpus::get()));
+        assert_eq!(options(&["--threads", "2", "--parallel"]).unwrap().threads, Some(2));
+        assert_eq!(options(&["run", "all"]).unwrap().threads, None);
+        assert_eq!(options(&["--threads", "0"]).err(), Some("Invalid number of threads: 0".to_string()));
+        assert!(options(&["--threads", "x"]).is_err());
+        assert!(options(&["--threads"]).is_err());
+
+        let parsed = options(&["--format", "json", "--text", "1", "--bench"]).unwrap();
+        assert_eq!((parsed.format, parsed.source, parsed.bench), (Format::Json, Some(InputSource::Inline("1".to_string())), true));
+        assert!(options(&["--format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn test_run_in_order() {
+        // Later items finish first, but are still returned in order
+        let items = (0..8).collect::<Vec<u64>>();
+        let slow = |&i: &u64| {
+            std::thread::sleep(std::time::Duration::from_millis(40 - 5 * i));
+            i
+        };
+        assert_eq!(run_in_order(&items, Some(4), slow).unwrap().collect::<Vec<_>>(), items);
+        assert_eq!(run_in_order(&items, None, slow).unwrap().collect::<Vec<_>>(), items);
+
+        let bench_parallel = Options { bench: true, threads: Some(2), ..options(&[]).unwrap() };
+        assert!(run("1", &bench_parallel).is_err());
+    }
+}
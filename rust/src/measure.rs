@@ -0,0 +1,159 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// How long and how often `bench` runs a function.
+#[derive(Clone, Debug)]
+pub struct BenchConfig {
+    /// Time spent running the function before any samples are taken.
+    pub warm_up: Duration,
+    /// Time to aim for when taking samples, within `min_samples` and `max_samples`.
+    pub target: Duration,
+    pub min_samples: usize,
+    pub max_samples: usize
+}
+
+impl BenchConfig {
+    /// Runs the function exactly once, without warm-up.
+    pub fn once() -> Self {
+        BenchConfig { warm_up: Duration::from_secs(0), target: Duration::from_secs(0), min_samples: 1, max_samples: 1 }
+    }
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig {
+            warm_up: Duration::from_millis(200),
+            target: Duration::from_secs(1),
+            min_samples: 10,
+            max_samples: 1000
+        }
+    }
+}
+
+/// Summary of the samples of a benchmark, all times in nanoseconds per call.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Stats {
+    pub samples: usize,
+    /// Calls timed together in each sample, more than one for functions too fast to time on their own.
+    pub iterations_per_sample: u64,
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub stddev: f64,
+    pub p95: f64,
+    pub max: f64,
+    /// Samples outside of the Tukey fences, 1.5 times the interquartile range beyond the quartiles.
+    pub outliers: usize
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[f64], iterations_per_sample: u64) -> Stats {
+        assert!(!samples.is_empty(), "No samples");
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let n = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let variance =
+            if n > 1 {
+                sorted.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+            } else {
+                0.0
+            };
+
+        let q1 = percentile(&sorted, 25.0);
+        let q3 = percentile(&sorted, 75.0);
+        let iqr = q3 - q1;
+        let (low, high) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+
+        Stats {
+            samples: n,
+            iterations_per_sample,
+            min: sorted[0],
+            median: percentile(&sorted, 50.0),
+            mean,
+            stddev: variance.sqrt(),
+            p95: percentile(&sorted, 95.0),
+            max: sorted[n - 1],
+            outliers: sorted.iter().filter(|&&s| s < low || s > high).count()
+        }
+    }
+
+    pub fn iterations(&self) -> u64 {
+        self.samples as u64 * self.iterations_per_sample
+    }
+}
+
+/// Linearly interpolated percentile `p` (0-100) of sorted, non-empty `sorted`.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Runs `f` according to `config` and returns the result of its first call with timing statistics.
+/// Results are passed through `black_box` so the calls whose result is dropped aren't optimised away.
+pub fn bench<T, F>(config: &BenchConfig, f: F) -> (T, Stats) where F: Fn() -> T {
+    let start = Instant::now();
+    let result = f();
+    let first = start.elapsed();
+
+    if config.max_samples <= 1 {
+        return (result, Stats::from_samples(&[first.as_nanos() as f64], 1));
+    }
+
+    let mut calls = 1;
+    while start.elapsed() < config.warm_up {
+        black_box(f());
+        calls += 1;
+    }
+    let estimate_ns = (start.elapsed().as_nanos() / calls).max(1);
+
+    // Time calls in batches of at least ~10µs so timer resolution doesn't dominate
+    let iterations_per_sample = (10_000 / estimate_ns).max(1) as u64;
+    let n_samples = (config.target.as_nanos() / (estimate_ns * iterations_per_sample as u128)) as usize;
+    let n_samples = n_samples.clamp(config.min_samples, config.max_samples);
+
+    let samples = (0..n_samples)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..iterations_per_sample {
+                black_box(f());
+            }
+            start.elapsed().as_nanos() as f64 / iterations_per_sample as f64
+        })
+        .collect::<Vec<_>>();
+
+    (result, Stats::from_samples(&samples, iterations_per_sample))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::from_samples(&[5.0, 1.0, 3.0, 2.0, 4.0, 100.0], 1);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.median, 3.5);
+        assert_eq!(stats.mean, 115.0 / 6.0);
+        assert_eq!(stats.max, 100.0);
+        assert_eq!(stats.p95, 76.25);
+        assert_eq!(stats.outliers, 1);
+
+        let stats = Stats::from_samples(&[7.0], 1);
+        assert_eq!((stats.median, stats.stddev, stats.p95), (7.0, 0.0, 7.0));
+    }
+
+    #[test]
+    fn test_bench() {
+        let config = BenchConfig { warm_up: Duration::from_millis(1), target: Duration::from_millis(5), min_samples: 3, max_samples: 50 };
+        let (result, stats) = bench(&config, || (1..100u64).sum::<u64>());
+        assert_eq!(result, 4950);
+        assert!((3..=50).contains(&stats.samples));
+        assert!(stats.min <= stats.median && stats.median <= stats.max);
+
+        let (_, stats) = bench(&BenchConfig::once(), || 1);
+        assert_eq!(stats.iterations(), 1);
+    }
+}
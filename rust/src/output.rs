@@ -0,0 +1,210 @@
+use std::str::FromStr;
+use crate::{Report, Stats};
+
+/// How the runner prints results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Text,
+    /// One JSON object per line.
+    Json,
+    /// A header followed by one row per day.
+    Csv
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("Invalid format: {} (expected text, json or csv)", s))
+        }
+    }
+}
+
+/// `s` as a quoted JSON string.
+pub fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c)
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// `s` as a CSV field, quoted only when needed.
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Splits a CSV row into its fields, undoing the quoting of `csv_field`.
+pub fn csv_split(row: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c)
+        }
+    }
+
+    fields
+}
+
+const STAT_FIELDS: [&str; 9] = ["samples", "iterations", "min_ns", "median_ns", "mean_ns", "stddev_ns", "p95_ns", "max_ns", "outliers"];
+
+fn stat_values(stats: &Stats) -> [String; 9] {
+    [
+        stats.samples.to_string(),
+        stats.iterations().to_string(),
+        format!("{:.0}", stats.min),
+        format!("{:.0}", stats.median),
+        format!("{:.0}", stats.mean),
+        format!("{:.0}", stats.stddev),
+        format!("{:.0}", stats.p95),
+        format!("{:.0}", stats.max),
+        stats.outliers.to_string()
+    ]
+}
+
+/// Formats a duration in ms, rounded to µs, or in ns when shorter than 1µs.
+fn ms(ns: f64) -> String {
+    if ns < 1_000.0 {
+        format!("{}ns", ns.round())
+    } else {
+        format!("{}ms", (ns / 1_000.0).round() / 1_000.0)
+    }
+}
+
+impl Report {
+    fn phases(&self) -> [(&'static str, &Stats); 3] {
+        [("parse", &self.parse), ("part1", &self.part1), ("part2", &self.part2)]
+    }
+
+    pub fn csv_header() -> String {
+        let mut header = "day,part1,part2,input_md5".to_string();
+        for phase in &["parse", "part1", "part2"] {
+            for field in &STAT_FIELDS {
+                header.push_str(&format!(",{}_{}", phase, field));
+            }
+        }
+        header
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Day {:02}\nPart1: {}\nPart2: {}\n", self.day, self.answers.part1, self.answers.part2);
+        if self.phases().iter().all(|(_, stats)| stats.samples == 1) {
+            text.push_str(&format!("It took: {}", ms(self.total_ns())));
+        } else {
+            text.push_str(&format!("It took: {} median", ms(self.total_ns())));
+            for (phase, stats) in &self.phases() {
+                text.push_str(&format!(
+                    "\n  {}: median {}, mean {} ± {}, min {}, p95 {}, {} samples{}, {} outliers",
+                    phase, ms(stats.median), ms(stats.mean), ms(stats.stddev), ms(stats.min), ms(stats.p95),
+                    stats.samples,
+                    if stats.iterations_per_sample > 1 { format!(" of {} calls", stats.iterations_per_sample) } else { String::new() },
+                    stats.outliers
+                ));
+            }
+        }
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        let timings = self.phases().iter()
+            .map(|(phase, stats)| {
+                let fields = STAT_FIELDS.iter()
+                    .zip(stat_values(stats).iter())
+                    .map(|(field, value)| format!("{}:{}", json_string(field), value))
+                    .collect::<Vec<_>>();
+                format!("{}:{{{}}}", json_string(phase), fields.join(","))
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\"day\":{},\"part1\":{},\"part2\":{},\"input_md5\":{},\"timings\":{{{}}}}}",
+            self.day, json_string(&self.answers.part1), json_string(&self.answers.part2),
+            json_string(&self.input_md5), timings.join(",")
+        )
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut row = format!("{},{},{},{}", self.day, csv_field(&self.answers.part1), csv_field(&self.answers.part2), self.input_md5);
+        for (_, stats) in &self.phases() {
+            for value in &stat_values(stats) {
+                row.push(',');
+                row.push_str(value);
+            }
+        }
+        row
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Answers;
+
+    fn report() -> Report {
+        Report {
+            day: 7,
+            answers: Answers { part1: "4".to_string(), part2: "a,\"b\"".to_string() },
+            parse: Stats::from_samples(&[1000.0], 1),
+            part1: Stats::from_samples(&[20.0, 30.0], 5),
+            part2: Stats::from_samples(&[300.0], 1),
+            input_md5: "d41d8cd98f00b204e9800998ecf8427e".to_string()
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(json_string("a\"b\\\n\u{1}"), "\"a\\\"b\\\\\\n\\u0001\"");
+        assert_eq!(report().to_json(), concat!(
+            "{\"day\":7,\"part1\":\"4\",\"part2\":\"a,\\\"b\\\"\",\"input_md5\":\"d41d8cd98f00b204e9800998ecf8427e\",\"timings\":{",
+            "\"parse\":{\"samples\":1,\"iterations\":1,\"min_ns\":1000,\"median_ns\":1000,\"mean_ns\":1000,\"stddev_ns\":0,\"p95_ns\":1000,\"max_ns\":1000,\"outliers\":0},",
+            "\"part1\":{\"samples\":2,\"iterations\":10,\"min_ns\":20,\"median_ns\":25,\"mean_ns\":25,\"stddev_ns\":7,\"p95_ns\":30,\"max_ns\":30,\"outliers\":0},",
+            "\"part2\":{\"samples\":1,\"iterations\":1,\"min_ns\":300,\"median_ns\":300,\"mean_ns\":300,\"stddev_ns\":0,\"p95_ns\":300,\"max_ns\":300,\"outliers\":0}}}"
+        ));
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(ms(0.4), "0ns");
+        assert_eq!(ms(999.4), "999ns");
+        assert_eq!(ms(1_234_567.0), "1.235ms");
+        assert_eq!(report().to_text(), concat!(
+            "Day 07\nPart1: 4\nPart2: a,\"b\"\nIt took: 0.001ms median\n",
+            "  parse: median 0.001ms, mean 0.001ms ± 0ns, min 0.001ms, p95 0.001ms, 1 samples, 0 outliers\n",
+            "  part1: median 25ns, mean 25ns ± 7ns, min 20ns, p95 30ns, 2 samples of 5 calls, 0 outliers\n",
+            "  part2: median 300ns, mean 300ns ± 0ns, min 300ns, p95 300ns, 1 samples, 0 outliers"
+        ));
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(Report::csv_header().split(',').count(), report().to_csv().split(',').count() - 1);
+        assert_eq!(csv_split("7,\"a,\"\"b\"\"\",,x")[..], ["7", "a,\"b\"", "", "x"]);
+        assert!(report().to_csv().starts_with("7,4,\"a,\"\"b\"\"\",d41d8cd98f00b204e9800998ecf8427e,1,1,1000,1000,1000,0,1000,1000,0,2,10,20,25,25,7,30,30,0,"));
+    }
+}
@@ -0,0 +1,127 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Describes where and why parsing of a puzzle input failed.
+/// Line and column are 1-based, with 0 meaning not (yet) known.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub day: u32,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub expected: String
+}
+
+impl ParseError {
+    /// Error for `token`, a slice of `line`, not being what was expected.
+    pub fn at(line: &str, token: &str, expected: &str) -> Self {
+        ParseError {
+            day: 0,
+            line: 0,
+            column: column_of(line, token),
+            text: token.to_string(),
+            expected: expected.to_string()
+        }
+    }
+
+    /// Error for `line` ending before what was expected.
+    pub fn at_end(line: &str, expected: &str) -> Self {
+        ParseError {
+            day: 0,
+            line: 0,
+            column: line.chars().count() + 1,
+            text: String::new(),
+            expected: expected.to_string()
+        }
+    }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    pub fn with_day(mut self, day: u32) -> Self {
+        self.day = day;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.day > 0 {
+            write!(f, "day {:02}, ", self.day)?;
+        }
+        if self.line > 0 {
+            write!(f, "line {}, ", self.line)?;
+        }
+        write!(f, "column {}: expected {}, ", self.column, self.expected)?;
+        if self.text.is_empty() {
+            write!(f, "found end of line")
+        } else {
+            write!(f, "found {:?}", self.text)
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+fn column_of(line: &str, token: &str) -> usize {
+    let offset = (token.as_ptr() as usize).wrapping_sub(line.as_ptr() as usize);
+    if offset <= line.len() && line.is_char_boundary(offset) {
+        line[..offset].chars().count() + 1
+    } else {
+        1
+    }
+}
+
+/// Parses every line of `input` with `f`, attaching line numbers to any error.
+pub fn parse_lines<T, F>(input: &str, f: F) -> Result<Vec<T>, ParseError>
+    where F: Fn(&str) -> Result<T, ParseError> {
+    input.lines()
+        .enumerate()
+        .map(|(i, line)| f(line).map_err(|e| e.with_line(i + 1)))
+        .collect()
+}
+
+/// Returns `token`, a slice of `line`, or an error if the line ended before it.
+pub fn expect_token<'a>(line: &str, token: Option<&'a str>, expected: &str) -> Result<&'a str, ParseError> {
+    token.ok_or_else(|| ParseError::at_end(line, expected))
+}
+
+/// Parses `token`, a slice of `line`, into a `T`.
+pub fn parse_token<T: FromStr>(line: &str, token: Option<&str>, expected: &str) -> Result<T, ParseError> {
+    let token = expect_token(line, token, expected)?;
+    token.parse::<T>().map_err(|_| ParseError::at(line, token, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column() {
+        let line = "acc +1x";
+        let mut parts = line.split(' ');
+        parts.next();
+
+        let err = parse_token::<i32>(line, parts.next(), "signed integer").unwrap_err();
+        assert_eq!(err.column, 5);
+        assert_eq!(err.text, "+1x");
+
+        let err = parse_token::<i32>(line, parts.next(), "signed integer").unwrap_err();
+        assert_eq!(err.column, 8);
+        assert_eq!(err.text, "");
+    }
+
+    #[test]
+    fn test_display() {
+        let err = parse_lines("1\n2\nx", |l| parse_token::<u32>(l, Some(l), "number"))
+            .unwrap_err()
+            .with_day(1);
+        assert_eq!(err.to_string(), "day 01, line 3, column 1: expected number, found \"x\"");
+
+        let err = ParseError::at_end("mask", "'='");
+        assert_eq!(err.to_string(), "column 5: expected '=', found end of line");
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use crate::input::input_dir;
+use crate::parse::ParseError;
+
+/// Known-good answers per day and part, read from a file like:
+///
+/// ```text
+/// # comments and blank lines are ignored
+/// [day01]
+/// part1 = 805731
+/// part2 = "192684960"
+/// ```
+#[derive(Debug, Default)]
+pub struct KnownAnswers {
+    answers: HashMap<(u32, u32), String>
+}
+
+impl KnownAnswers {
+    /// `answers.toml` next to the input files.
+    pub fn default_path() -> PathBuf {
+        input_dir().join("answers.toml")
+    }
+
+    pub fn parse(input: &str) -> Result<KnownAnswers, ParseError> {
+        let mut answers = HashMap::new();
+        let mut day = None;
+
+        for (i, line) in input.lines().enumerate() {
+            let at = |token: &str, expected: &str| ParseError::at(line, token, expected).with_line(i + 1);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[') {
+                let section = section.strip_suffix(']').ok_or_else(|| at(line, "'[dayNN]'"))?;
+                let d = section.strip_prefix("day").ok_or_else(|| at(section, "'dayNN'"))?;
+                day = Some(d.parse::<u32>().map_err(|_| at(d, "day number"))?);
+                continue;
+            }
+
+            let day = day.ok_or_else(|| at(line, "'[dayNN]' before answers"))?;
+            let mut parts = line.splitn(2, '=').map(|s| s.trim());
+            let key = parts.next().unwrap_or(line);
+            let part = match key {
+                "part1" => 1,
+                "part2" => 2,
+                _ => return Err(at(key, "'part1' or 'part2'"))
+            };
+            let value = parts.next().ok_or_else(|| at(&line[line.len()..], "'='"))?;
+            let value =
+                if let Some(quoted) = value.strip_prefix('"') {
+                    quoted.strip_suffix('"').ok_or_else(|| at(value, "closing '\"'"))?
+                } else if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit() || c == '-') {
+                    value
+                } else {
+                    return Err(at(value, "quoted string or integer"));
+                };
+
+            answers.insert((day, part), value.to_string());
+        }
+
+        Ok(KnownAnswers { answers })
+    }
+
+    pub fn get(&self, day: u32, part: u32) -> Option<&str> {
+        self.answers.get(&(day, part)).map(|a| a.as_str())
+    }
+
+    pub fn verify(&self, day: u32, part: u32, actual: &str) -> Verdict {
+        match self.get(day, part) {
+            Some(expected) if expected == actual => Verdict::Pass,
+            Some(expected) => Verdict::Fail { expected: expected.to_string(), actual: actual.to_string() },
+            None => Verdict::Missing
+        }
+    }
+}
+
+/// Outcome of comparing an answer with the known one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Pass,
+    Fail { expected: String, actual: String },
+    Missing
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "pass"),
+            Verdict::Fail { expected, actual } => write!(f, "FAIL (expected {}, got {})", expected, actual),
+            Verdict::Missing => write!(f, "missing")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str =
+       "# Known answers
+        [day01]
+        part1 = 514579
+        part2 = \"241861950\"
+
+        [day08]
+        part1 = 5";
+
+    fn as_input(s: &str) -> KnownAnswers {
+        KnownAnswers::parse(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n")).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let known = as_input(INPUT);
+        assert_eq!(known.get(1, 1), Some("514579"));
+        assert_eq!(known.get(1, 2), Some("241861950"));
+        assert_eq!(known.get(8, 2), None);
+
+        let err = KnownAnswers::parse("[day01]\npart3 = 1").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 1: expected 'part1' or 'part2', found \"part3\"");
+    }
+
+    #[test]
+    fn test_verify() {
+        let known = as_input(INPUT);
+        assert_eq!(known.verify(1, 1, "514579"), Verdict::Pass);
+        assert_eq!(known.verify(1, 1, "1"), Verdict::Fail { expected: "514579".to_string(), actual: "1".to_string() });
+        assert_eq!(known.verify(8, 2, "8"), Verdict::Missing);
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use crate::output::json_string;
+use crate::parse::{ParseError, parse_token};
+
+/// How execution continues after an instruction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Flow {
+    /// On to the next instruction.
+    Next,
+    /// Relative jump by the given offset.
+    Jump(isize),
+    /// Stop, terminating the program.
+    Halt
+}
+
+/// An instruction set the `Machine` can run.
+pub trait Instruction {
+    /// Executes the instruction on `registers`, returning where to continue.
+    fn execute(&self, registers: &mut [i64]) -> Flow;
+
+    /// Whether where execution continues depends on the registers, so that running it
+    /// a second time doesn't mean the program loops.
+    fn is_conditional(&self) -> bool {
+        false
+    }
+}
+
+/// Why a `Machine` stopped running.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Status {
+    /// Halted, or reached the position right after the last instruction.
+    Terminated,
+    /// About to run the instruction at `pc` again as detected by `LoopDetection`, so it would never terminate.
+    Looped(usize),
+    /// Jumped to `pc`, outside of the program.
+    OutOfBounds(isize),
+    /// Ran out of its step budget.
+    StepLimit
+}
+
+/// What counts as revisiting a state when detecting infinite loops, by default `Pc` for
+/// programs without conditional instructions and `State` for programs with them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoopDetection {
+    Off,
+    /// Any instruction run twice, which is only a loop when jumps don't depend on registers.
+    Pc,
+    /// An instruction run twice with the same register values.
+    State
+}
+
+#[derive(Clone, Debug)]
+pub struct Machine<'a, I> {
+    program: &'a [I],
+    pub pc: isize,
+    pub registers: Vec<i64>,
+    /// Instructions run so far.
+    pub steps: usize,
+    step_limit: Option<usize>,
+    loop_detection: LoopDetection,
+    halted: bool
+}
+
+impl<'a, I: Instruction> Machine<'a, I> {
+    /// Machine at the start of `program` with `registers` registers set to 0, detecting loops by `pc`
+    /// unless the program has conditional instructions, whose pc can repeat without looping.
+    pub fn new(program: &'a [I], registers: usize) -> Self {
+        let loop_detection = if program.iter().any(I::is_conditional) { LoopDetection::State } else { LoopDetection::Pc };
+        Machine {
+            program,
+            pc: 0,
+            registers: vec![0; registers],
+            steps: 0,
+            step_limit: None,
+            loop_detection,
+            halted: false
+        }
+    }
+
+    pub fn with_step_limit(mut self, step_limit: usize) -> Self {
+        self.step_limit = Some(step_limit);
+        self
+    }
+
+    pub fn with_loop_detection(mut self, loop_detection: LoopDetection) -> Self {
+        self.loop_detection = loop_detection;
+        self
+    }
+
+    pub fn loop_detection(&self) -> LoopDetection {
+        self.loop_detection
+    }
+
+    pub fn program(&self) -> &'a [I] {
+        self.program
+    }
+
+    /// The instruction about to run, `None` once stopped.
+    pub fn current(&self) -> Option<&'a I> {
+        if self.halted || self.pc < 0 {
+            None
+        } else {
+            self.program.get(self.pc as usize)
+        }
+    }
+
+    /// Why the machine can't run any further, `None` if it can.
+    pub fn status(&self) -> Option<Status> {
+        let len = self.program.len() as isize;
+        if self.halted || self.pc == len {
+            Some(Status::Terminated)
+        } else if self.pc < 0 || self.pc > len {
+            Some(Status::OutOfBounds(self.pc))
+        } else {
+            None
+        }
+    }
+
+    /// Runs a single instruction, returning the status if the machine stopped.
+    pub fn step(&mut self) -> Option<Status> {
+        let instruction = match self.current() {
+            Some(instruction) => instruction,
+            None => return self.status()
+        };
+
+        match instruction.execute(&mut self.registers) {
+            Flow::Next => self.pc += 1,
+            Flow::Jump(offset) => self.pc = self.pc.saturating_add(offset),
+            Flow::Halt => self.halted = true
+        }
+        self.steps += 1;
+
+        self.status()
+    }
+
+    /// Runs until the program stops, loops or the step budget runs out.
+    pub fn run(&mut self) -> Status {
+        self.run_with(|_, _| ()).0
+    }
+
+    /// Like `run` but recording every instruction run in a `Trace`.
+    pub fn trace(&mut self) -> Trace {
+        let mut steps = vec![];
+        let start = self.steps;
+        let (status, loop_start) = self.run_with(|pc, registers| steps.push(TraceStep { pc, registers: registers.to_vec() }));
+
+        let (loop_entry, loop_body) = match (status, loop_start) {
+            (Status::Looped(entry), Some(loop_start)) => (Some(entry), steps[(loop_start - start)..].iter().map(|step| step.pc).collect()),
+            _ => (None, vec![])
+        };
+
+        Trace { steps, status, loop_entry, loop_body }
+    }
+
+    /// Runs like `run`, calling `after_step` with the pc of every instruction run and the registers after it.
+    /// For loops also returns the step at which the repeated state was first reached, where the loop starts.
+    fn run_with<F>(&mut self, mut after_step: F) -> (Status, Option<usize>) where F: FnMut(usize, &[i64]) {
+        let mut visited_pcs = vec![None; self.program.len()];
+        let mut visited_states = HashMap::new();
+
+        loop {
+            if let Some(status) = self.status() {
+                return (status, None);
+            }
+            if self.step_limit.is_some_and(|limit| self.steps >= limit) {
+                return (Status::StepLimit, None);
+            }
+
+            let pc = self.pc as usize;
+            let first_seen = match self.loop_detection {
+                LoopDetection::Off => None,
+                LoopDetection::Pc => visited_pcs[pc].replace(self.steps),
+                LoopDetection::State => visited_states.insert((pc, self.registers.clone()), self.steps)
+            };
+            if let Some(step) = first_seen {
+                return (Status::Looped(pc), Some(step));
+            }
+
+            self.step();
+            after_step(pc, &self.registers);
+        }
+    }
+}
+
+/// An instruction run by `Machine::trace` and the registers after it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TraceStep {
+    pub pc: usize,
+    pub registers: Vec<i64>
+}
+
+/// Everything a machine ran and why it stopped.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+    pub status: Status,
+    /// The instruction that was about to run a second time, for looped programs.
+    pub loop_entry: Option<usize>,
+    /// The pcs run from the first time the looping state was reached on, in order.
+    pub loop_body: Vec<usize>
+}
+
+impl Trace {
+    /// A table of the steps run followed by the outcome, with registers named by `register_names`.
+    pub fn to_text<I: fmt::Display>(&self, program: &[I], register_names: &[&str]) -> String {
+        let mut lines = vec![format!("{:>6} {:>5}  {:<12} {}", "step", "pc", "instruction", register_names.join(" "))];
+        for (i, step) in self.steps.iter().enumerate() {
+            let registers = step.registers.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(" ");
+            lines.push(format!("{:>6} {:>5}  {:<12} {}", i + 1, step.pc, program[step.pc].to_string(), registers));
+        }
+
+        lines.push(String::new());
+        lines.push(match self.status {
+            Status::Terminated => "Terminated".to_string(),
+            Status::Looped(pc) => format!("Looped, pc {} was about to run again", pc),
+            Status::OutOfBounds(pc) => format!("Jumped out of bounds to pc {}", pc),
+            Status::StepLimit => format!("Stopped after the step limit of {} steps", self.steps.len())
+        });
+        if let Some(entry) = self.loop_entry {
+            lines.push(format!("Loop entry: pc {}", entry));
+            lines.push(format!("Loop body ({} instructions):", self.loop_body.len()));
+            for &pc in &self.loop_body {
+                lines.push(format!("{:>5}  {}", pc, program[pc]));
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self, register_names: &[&str]) -> String {
+        let registers = |values: &[i64]| register_names.iter()
+            .zip(values)
+            .map(|(name, value)| format!("{}:{}", json_string(name), value))
+            .collect::<Vec<_>>()
+            .join(",");
+        let steps = self.steps.iter()
+            .map(|step| format!("{{\"pc\":{},{}}}", step.pc, registers(&step.registers)))
+            .collect::<Vec<_>>();
+        let status = match self.status {
+            Status::Terminated => "\"status\":\"terminated\"".to_string(),
+            Status::Looped(pc) => format!("\"status\":\"looped\",\"pc\":{}", pc),
+            Status::OutOfBounds(pc) => format!("\"status\":\"out_of_bounds\",\"pc\":{}", pc),
+            Status::StepLimit => "\"status\":\"step_limit\"".to_string()
+        };
+        let loop_body = self.loop_body.iter().map(|pc| pc.to_string()).collect::<Vec<_>>();
+
+        format!(
+            "{{{},\"steps\":[{}],\"loop_entry\":{},\"loop_body\":[{}]}}",
+            status, steps.join(","), self.loop_entry.map_or("null".to_string(), |pc| pc.to_string()), loop_body.join(",")
+        )
+    }
+}
+
+/// Name of register `idx`, registers being named `a` to `z`.
+pub fn register_name(idx: usize) -> char {
+    (b'a' + idx as u8) as char
+}
+
+/// A general purpose instruction set, a superset of the handheld console's `nop`, `acc` and `jmp`,
+/// where `acc` adds to register `a`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Op {
+    Nop(i64),
+    Acc(i64),
+    Jmp(isize),
+    Set(usize, i64),
+    Add(usize, i64),
+    Mul(usize, i64),
+    /// Jump if the register is zero.
+    Jz(usize, isize),
+    /// Jump if the register is not zero.
+    Jnz(usize, isize),
+    Hlt
+}
+
+impl Op {
+    /// Number of registers needed to run `program`.
+    pub fn registers_used(program: &[Op]) -> usize {
+        program.iter()
+            .map(|op| match *op {
+                Op::Set(r, _) | Op::Add(r, _) | Op::Mul(r, _) | Op::Jz(r, _) | Op::Jnz(r, _) => r + 1,
+                _ => 1
+            })
+            .max()
+            .unwrap_or(1)
+    }
+}
+
+impl Instruction for Op {
+    fn execute(&self, registers: &mut [i64]) -> Flow {
+        match *self {
+            Op::Nop(_) => Flow::Next,
+            Op::Acc(n) => {
+                registers[0] = registers[0].wrapping_add(n);
+                Flow::Next
+            },
+            Op::Jmp(offset) => Flow::Jump(offset),
+            Op::Set(r, n) => {
+                registers[r] = n;
+                Flow::Next
+            },
+            Op::Add(r, n) => {
+                registers[r] = registers[r].wrapping_add(n);
+                Flow::Next
+            },
+            Op::Mul(r, n) => {
+                registers[r] = registers[r].wrapping_mul(n);
+                Flow::Next
+            },
+            Op::Jz(r, offset) => if registers[r] == 0 { Flow::Jump(offset) } else { Flow::Next },
+            Op::Jnz(r, offset) => if registers[r] != 0 { Flow::Jump(offset) } else { Flow::Next },
+            Op::Hlt => Flow::Halt
+        }
+    }
+
+    fn is_conditional(&self) -> bool {
+        matches!(self, Op::Jz(..) | Op::Jnz(..))
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Op::Nop(n) => write!(f, "nop {:+}", n),
+            Op::Acc(n) => write!(f, "acc {:+}", n),
+            Op::Jmp(offset) => write!(f, "jmp {:+}", offset),
+            Op::Set(r, n) => write!(f, "set {} {:+}", register_name(r), n),
+            Op::Add(r, n) => write!(f, "add {} {:+}", register_name(r), n),
+            Op::Mul(r, n) => write!(f, "mul {} {:+}", register_name(r), n),
+            Op::Jz(r, offset) => write!(f, "jz {} {:+}", register_name(r), offset),
+            Op::Jnz(r, offset) => write!(f, "jnz {} {:+}", register_name(r), offset),
+            Op::Hlt => write!(f, "hlt")
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const EXPECTED_OPCODE: &str = "opcode 'nop', 'acc', 'jmp', 'set', 'add', 'mul', 'jz', 'jnz' or 'hlt'";
+        let mut parts = s.split_ascii_whitespace();
+        let opcode = parts.next().ok_or_else(|| ParseError::at_end(s, EXPECTED_OPCODE))?;
+
+        let mut register = || -> Result<usize, ParseError> {
+            let name = parts.next().ok_or_else(|| ParseError::at_end(s, "register 'a'-'z'"))?;
+            match name.as_bytes() {
+                [r @ b'a'..=b'z'] => Ok((r - b'a') as usize),
+                _ => Err(ParseError::at(s, name, "register 'a'-'z'"))
+            }
+        };
+
+        let op = match opcode {
+            "nop" => Op::Nop(parse_token(s, parts.next(), "signed integer argument")?),
+            "acc" => Op::Acc(parse_token(s, parts.next(), "signed integer argument")?),
+            "jmp" => Op::Jmp(parse_token(s, parts.next(), "signed jump offset")?),
+            "set" => Op::Set(register()?, parse_token(s, parts.next(), "signed integer argument")?),
+            "add" => Op::Add(register()?, parse_token(s, parts.next(), "signed integer argument")?),
+            "mul" => Op::Mul(register()?, parse_token(s, parts.next(), "signed integer argument")?),
+            "jz" => Op::Jz(register()?, parse_token(s, parts.next(), "signed jump offset")?),
+            "jnz" => Op::Jnz(register()?, parse_token(s, parts.next(), "signed jump offset")?),
+            "hlt" => Op::Hlt,
+            _ => return Err(ParseError::at(s, opcode, EXPECTED_OPCODE))
+        };
+
+        match parts.next() {
+            Some(extra) => Err(ParseError::at(s, extra, "end of line")),
+            None => Ok(op)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_lines;
+
+    fn as_program(s: &str) -> Vec<Op> {
+        parse_lines(&s.split('\n').map(|s| s.trim()).collect::<Vec<_>>().join("\n"), str::parse::<Op>).unwrap()
+    }
+
+    #[test]
+    fn test_status() {
+        let program = as_program(
+           "nop +0
+            acc +1
+            jmp +4
+            acc +3
+            jmp -3
+            acc -99
+            acc +1
+            jmp -4
+            acc +6");
+        let mut machine = Machine::new(&program, 1);
+        assert_eq!(machine.run(), Status::Looped(1));
+        assert_eq!(machine.registers[0], 5);
+
+        let mut machine = Machine::new(&program, 1).with_step_limit(3);
+        assert_eq!(machine.run(), Status::StepLimit);
+        assert_eq!(machine.steps, 3);
+
+        let trace = Machine::new(&program, 1).trace();
+        assert_eq!(trace.steps.iter().map(|s| (s.pc, s.registers[0])).collect::<Vec<_>>(),
+            vec![(0, 0), (1, 1), (2, 1), (6, 2), (7, 2), (3, 5), (4, 5)]);
+        assert_eq!(trace.loop_entry, Some(1));
+        assert_eq!(trace.loop_body, vec![1, 2, 6, 7, 3, 4]);
+        assert_eq!(Machine::new(&program[..2], 1).trace().to_json(&["acc"]),
+            "{\"status\":\"terminated\",\"steps\":[{\"pc\":0,\"acc\":0},{\"pc\":1,\"acc\":1}],\"loop_entry\":null,\"loop_body\":[]}");
+
+        let program = as_program("acc +1\njmp -2");
+        assert_eq!(Machine::new(&program, 1).run(), Status::OutOfBounds(-1));
+    }
+
+    #[test]
+    fn test_trace_state_loop() {
+        // pc 1 first runs with b = 1, the loop only starts when it runs with b = 0
+        let program = as_program("set b +1\nnop +0\njz b -1\nset b +0\njmp -3");
+        let trace = Machine::new(&program, 2).trace();
+        assert_eq!(trace.steps.iter().map(|s| s.pc).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 1, 2]);
+        assert_eq!(trace.loop_entry, Some(1));
+        assert_eq!(trace.loop_body, vec![1, 2]);
+
+        let mut machine = Machine::new(&program, 2);
+        machine.step();
+        assert_eq!(machine.trace().loop_body, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_conditional_jumps() {
+        // 5 to the power of 5 in a
+        let program = as_program(
+           "set a +1
+            set b +5
+            jz b +4
+            mul a +5
+            add b -1
+            jnz b -2
+            hlt
+            acc +1000");
+        let mut machine = Machine::new(&program, Op::registers_used(&program)).with_loop_detection(LoopDetection::State);
+        assert_eq!(machine.run(), Status::Terminated);
+        assert_eq!(machine.registers, vec![3125, 0]);
+        assert_eq!(program[4].to_string(), "add b -1");
+        assert_eq!(program[5].to_string(), "jnz b -2");
+        assert!("jnz 1 +2".parse::<Op>().is_err());
+        assert!("hlt 1".parse::<Op>().is_err());
+    }
+
+    #[test]
+    fn test_default_loop_detection() {
+        // Counts b down from 3, running the jnz at pc 2 three times without looping
+        let program = as_program("set b +3\nadd b -1\njnz b -1\nhlt");
+        let machine = Machine::new(&program, 2);
+        assert_eq!(machine.loop_detection(), LoopDetection::State);
+        assert_eq!(machine.clone().run(), Status::Terminated);
+        assert_eq!(machine.with_loop_detection(LoopDetection::Pc).run(), Status::Looped(1));
+
+        let program = as_program("set b +3\njmp -1");
+        assert_eq!(Machine::new(&program, 2).loop_detection(), LoopDetection::Pc);
+        assert_eq!(Machine::new(&program, 2).run(), Status::Looped(0));
+    }
+}
@@ -8,10 +8,12 @@ if [ -z "$DAY" ]
 fi
 
 DST="src/day$DAY.rs"
-cp template/day.rs "$DST"
+sed -e "s/DayNN/Day$DAY/g" -e "s/= NN;/= $((10#$DAY));/" template/day.rs > "$DST"
 echo "$DST created"
 
-echo "
-[[bin]]
-name = \"day$DAY\"
-path = \"$DST\"" >> Cargo.toml
\ No newline at end of file
+LAST_MOD=`grep -n '^pub mod day' src/lib.rs | tail -n 1 | cut -d ':' -f1`
+sed -i "${LAST_MOD}a pub mod day$DAY;" src/lib.rs
+
+LAST_DAY=`grep -n '^    Day::of::<day' src/lib.rs | tail -n 1 | cut -d ':' -f1`
+sed -i "${LAST_DAY}a \\    Day::of::<day$DAY::Day$DAY>()," src/lib.rs
+echo "day$DAY registered in src/lib.rs"
\ No newline at end of file